@@ -0,0 +1,443 @@
+//! An expectimax player.
+//!
+//! The search alternates between the player, who picks the direction with the best
+//! expected value, and the spawner, whose tile is averaged over every empty cell and
+//! every value it can place. Leaves are scored by a [`Heuristic`].
+//!
+//! The search runs on any [`Grid`]; [`Expectimax::best_move`] packs 4x4 games into a
+//! [`BitBoard`] first, which is many times faster.
+//!
+//! Bots that only need a move can use any [`Player`], including the much weaker
+//! [`Greedy`] and [`Random`] players kept as baselines.
+//!
+//! [`worst_spawn`] plays the other side: the adversary behind
+//! [`Spawner::Evil`](crate::game::Spawner::Evil), which searches for the spawn that
+//! hurts the player most.
+
+use crate::{
+    bitboard::BitBoard,
+    board::{Board, Grid},
+    game::{Direction, Game},
+};
+use rand::{seq::SliceRandom, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::collections::HashMap;
+
+/// Value of a position in which no move is possible.
+const LOSS: f64 = -1.0e6;
+/// Roughly how much work [`worst_spawn`] may do per spawn, in positions searched times
+/// cells on the board.
+const SPAWN_BUDGET: f64 = 1.0e6;
+
+/// Scores a position; higher is better for the player.
+pub trait Heuristic<G = Board> {
+    fn evaluate(&self, board: &G) -> f64;
+}
+
+impl<G, F: Fn(&G) -> f64> Heuristic<G> for F {
+    fn evaluate(&self, board: &G) -> f64 {
+        self(board)
+    }
+}
+
+/// A weighted sum of the classic 2048 features.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Weighted {
+    pub empty: f64,
+    pub monotonicity: f64,
+    pub smoothness: f64,
+    pub corner: f64,
+}
+
+impl Default for Weighted {
+    fn default() -> Self {
+        Self {
+            empty: 2.7,
+            monotonicity: 1.0,
+            smoothness: 0.1,
+            corner: 1.0,
+        }
+    }
+}
+
+impl<G: Grid> Heuristic<G> for Weighted {
+    fn evaluate(&self, board: &G) -> f64 {
+        self.empty * empty_cells(board)
+            + self.monotonicity * monotonicity(board)
+            + self.smoothness * smoothness(board)
+            + self.corner * corner(board)
+    }
+}
+
+fn log2(val: Option<u32>) -> f64 {
+    val.map_or(0.0, |val| f64::from(val).log2())
+}
+
+/// The number of empty cells.
+pub fn empty_cells<G: Grid>(board: &G) -> f64 {
+    board.empty_cells().len() as f64
+}
+
+/// How far rows and columns are from sorted, as a penalty (0 when all are sorted).
+///
+/// Each line is measured in the direction it is closest to being sorted in, using the
+/// log2 of the tiles.
+pub fn monotonicity<G: Grid>(board: &G) -> f64 {
+    let mut penalty = 0.0;
+    let lines = (0..board.rows())
+        .map(|r| {
+            (0..board.cols())
+                .map(|c| board.get(r, c))
+                .collect::<Vec<_>>()
+        })
+        .chain((0..board.cols()).map(|c| (0..board.rows()).map(|r| board.get(r, c)).collect()));
+    for line in lines {
+        let (mut increasing, mut decreasing) = (0.0, 0.0);
+        for pair in line.windows(2) {
+            let (a, b) = (log2(pair[0]), log2(pair[1]));
+            if a < b {
+                increasing += b - a;
+            } else {
+                decreasing += a - b;
+            }
+        }
+        penalty += f64::min(increasing, decreasing);
+    }
+    -penalty
+}
+
+/// How different neighbouring tiles are, as a penalty (0 when all neighbours are equal).
+pub fn smoothness<G: Grid>(board: &G) -> f64 {
+    let mut penalty = 0.0;
+    for r in 0..board.rows() {
+        for c in 0..board.cols() {
+            let val = match board.get(r, c) {
+                Some(val) => f64::from(val).log2(),
+                None => continue,
+            };
+            for (nr, nc) in [(r + 1, c), (r, c + 1)] {
+                if nr < board.rows() && nc < board.cols() && board.get(nr, nc).is_some() {
+                    penalty += (val - log2(board.get(nr, nc))).abs();
+                }
+            }
+        }
+    }
+    -penalty
+}
+
+/// The log2 of the largest tile if it sits in a corner, otherwise 0.
+pub fn corner<G: Grid>(board: &G) -> f64 {
+    let max_tile = board.max_tile();
+    let (last_row, last_col) = (board.rows() - 1, board.cols() - 1);
+    let cornered = [(0, 0), (0, last_col), (last_row, 0), (last_row, last_col)]
+        .into_iter()
+        .any(|(r, c)| board.get(r, c) == Some(max_tile));
+    if cornered && max_tile > 0 {
+        f64::from(max_tile).log2()
+    } else {
+        0.0
+    }
+}
+
+/// Chooses moves by expectimax search to a fixed depth.
+#[derive(Clone, Debug)]
+pub struct Expectimax<H = Weighted> {
+    /// Number of player moves to look ahead, counting the move being chosen.
+    pub depth: u32,
+    pub heuristic: H,
+    /// Spawns whose combined probability falls below this are not searched further.
+    pub min_probability: f64,
+}
+
+impl Default for Expectimax {
+    fn default() -> Self {
+        Self::new(3, Weighted::default())
+    }
+}
+
+impl<H> Expectimax<H> {
+    pub fn new(depth: u32, heuristic: H) -> Self {
+        Self {
+            depth,
+            heuristic,
+            min_probability: 1.0e-4,
+        }
+    }
+
+    /// The best move from the game's current position, or `None` if no move is possible.
+    pub fn best_move(&self, game: &Game) -> Option<Direction>
+    where
+        H: Heuristic<Board> + Heuristic<BitBoard>,
+    {
+        let odds = game.spawn_odds();
+        let packed = BitBoard::from_board(game.board())
+            .filter(|_| odds.iter().all(|&(val, _)| BitBoard::merges(val)));
+        match packed {
+            Some(board) => self.best_move_on(&board, &odds),
+            None => self.best_move_on(game.board(), &odds),
+        }
+    }
+
+    /// The best move on `board` when spawns follow `odds`, a list of values and their
+    /// probabilities.
+    pub fn best_move_on<G: Grid>(&self, board: &G, odds: &[(u32, f64)]) -> Option<Direction>
+    where
+        H: Heuristic<G>,
+    {
+        let mut search = Search {
+            expectimax: self,
+            odds,
+            cache: HashMap::new(),
+        };
+        let mut best: Option<(Direction, f64)> = None;
+        for direction in Direction::ALL {
+            let mut next = board.clone();
+            if !next.shift(direction).1 {
+                continue;
+            }
+            let value = search.chance(&next, self.depth.saturating_sub(1), 1.0);
+            if best.is_none_or(|(_, best)| value > best) {
+                best = Some((direction, value));
+            }
+        }
+        best.map(|(direction, _)| direction)
+    }
+}
+
+/// The empty cell and value, out of `values`, that leave the player worst off after
+/// `depth` replies, judged by the default [`Weighted`] heuristic.
+///
+/// The search grows with the number of empty cells to the power of `depth`, so while
+/// the board is too open for `depth` to take about a tenth of a second it looks fewer
+/// replies ahead, though always at least one.
+///
+/// Returns `None` if the board is full. Like [`Expectimax::best_move`], 4x4 boards are
+/// searched as a [`BitBoard`].
+pub fn worst_spawn(board: &Board, values: &[u32], depth: u32) -> Option<((usize, usize), u32)> {
+    let spawns = (board.empty_cells().len() * values.len()) as f64;
+    let cells = (board.rows() * board.cols()) as f64;
+    let depth = (1..=depth)
+        .rev()
+        .find(|&depth| spawns.powi(depth as i32) * cells <= SPAWN_BUDGET)
+        .unwrap_or(1);
+    let heuristic = Weighted::default();
+    let packed =
+        BitBoard::from_board(board).filter(|_| values.iter().all(|&val| BitBoard::merges(val)));
+    match packed {
+        Some(packed) => worst_spawn_on(&packed, values, depth, &heuristic),
+        None => worst_spawn_on(board, values, depth, &heuristic),
+    }
+}
+
+/// The spawn on `board` that minimises the player's best outcome, by minimax to
+/// `depth` player moves with the adversary choosing every spawn in between.
+///
+/// Ties go to the first cell in reading order, then the first value.
+pub fn worst_spawn_on<G: Grid, H: Heuristic<G>>(
+    board: &G,
+    values: &[u32],
+    depth: u32,
+    heuristic: &H,
+) -> Option<((usize, usize), u32)> {
+    let adversary = Adversary { heuristic, values };
+    let mut worst: Option<(_, f64)> = None;
+    for (r, c) in board.empty_cells() {
+        for &val in values {
+            let mut next = board.clone();
+            next.set(r, c, Some(val));
+            let beta = worst.map_or(f64::INFINITY, |(_, worst)| worst);
+            let value = adversary.max(&next, depth.max(1), f64::NEG_INFINITY, beta);
+            if worst.is_none_or(|(_, worst)| value < worst) {
+                worst = Some((((r, c), val), value));
+            }
+        }
+    }
+    worst.map(|(spawn, _)| spawn)
+}
+
+/// The search behind [`worst_spawn_on`], a minimax with alpha-beta pruning: `alpha` is
+/// the least the player is already sure of and `beta` the most the adversary will allow.
+struct Adversary<'a, H> {
+    heuristic: &'a H,
+    values: &'a [u32],
+}
+
+impl<H> Adversary<'_, H> {
+    /// The player picks the best move, with `depth` moves still to search.
+    fn max<G: Grid>(&self, board: &G, depth: u32, mut alpha: f64, beta: f64) -> f64
+    where
+        H: Heuristic<G>,
+    {
+        let mut best = LOSS;
+        for direction in Direction::ALL {
+            let mut next = board.clone();
+            if next.shift(direction).1 {
+                best = best.max(self.min(&next, depth - 1, alpha, beta));
+                if best >= beta {
+                    return best;
+                }
+                alpha = alpha.max(best);
+            }
+        }
+        best
+    }
+
+    /// The adversary picks the worst spawn. A move always leaves an empty cell.
+    fn min<G: Grid>(&self, board: &G, depth: u32, alpha: f64, mut beta: f64) -> f64
+    where
+        H: Heuristic<G>,
+    {
+        if depth == 0 {
+            return self.heuristic.evaluate(board);
+        }
+        let mut worst = f64::INFINITY;
+        for (r, c) in board.empty_cells() {
+            for &val in self.values {
+                let mut next = board.clone();
+                next.set(r, c, Some(val));
+                worst = worst.min(self.max(&next, depth, alpha, beta));
+                if worst <= alpha {
+                    return worst;
+                }
+                beta = beta.min(worst);
+            }
+        }
+        worst
+    }
+}
+
+/// Anything that picks moves.
+pub trait Player {
+    /// The move to make from the game's current position, or `None` if no move is
+    /// possible.
+    fn choose(&mut self, game: &Game) -> Option<Direction>;
+}
+
+impl<H> Player for Expectimax<H>
+where
+    H: Heuristic<Board> + Heuristic<BitBoard>,
+{
+    fn choose(&mut self, game: &Game) -> Option<Direction> {
+        self.best_move(game)
+    }
+}
+
+/// Makes whichever move scores the most points right away, then whichever leaves the
+/// most empty cells.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Greedy;
+
+impl Player for Greedy {
+    fn choose(&mut self, game: &Game) -> Option<Direction> {
+        Direction::ALL
+            .into_iter()
+            .filter_map(|direction| {
+                let mut board = game.board().clone();
+                let (gained, moved) = board.shift(direction);
+                moved.then(|| (direction, (gained, board.empty_cells().len())))
+            })
+            .max_by_key(|&(_, key)| key)
+            .map(|(direction, _)| direction)
+    }
+}
+
+/// Makes any legal move, each as likely as the others.
+#[derive(Clone, Debug)]
+pub struct Random {
+    rng: ChaCha8Rng,
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: ChaCha8Rng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Player for Random {
+    fn choose(&mut self, game: &Game) -> Option<Direction> {
+        game.legal_moves().choose(&mut self.rng).copied()
+    }
+}
+
+/// The built-in players, as chosen by name on the command line.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlayerKind {
+    Expectimax,
+    Greedy,
+    Random,
+}
+
+impl PlayerKind {
+    /// Every kind with its name.
+    pub const ALL: [(&'static str, PlayerKind); 3] = [
+        ("expectimax", PlayerKind::Expectimax),
+        ("greedy", PlayerKind::Greedy),
+        ("random", PlayerKind::Random),
+    ];
+
+    pub fn from_name(name: &str) -> Option<PlayerKind> {
+        Self::ALL
+            .iter()
+            .find(|&&(kind_name, _)| kind_name == name)
+            .map(|&(_, kind)| kind)
+    }
+
+    /// A player of this kind. `depth` is the expectimax search depth and `seed` drives
+    /// the random player.
+    pub fn player(self, depth: u32, seed: u64) -> Box<dyn Player> {
+        match self {
+            PlayerKind::Expectimax => Box::new(Expectimax::new(depth, Weighted::default())),
+            PlayerKind::Greedy => Box::new(Greedy),
+            PlayerKind::Random => Box::new(Random::new(seed)),
+        }
+    }
+}
+
+/// State shared across one search.
+struct Search<'a, H, G> {
+    expectimax: &'a Expectimax<H>,
+    odds: &'a [(u32, f64)],
+    /// Values of chance nodes already searched, by position and remaining depth.
+    cache: HashMap<(G, u32), f64>,
+}
+
+impl<H: Heuristic<G>, G: Grid> Search<'_, H, G> {
+    /// The player picks the best move.
+    fn max(&mut self, board: &G, depth: u32, probability: f64) -> f64 {
+        let mut best = LOSS;
+        for direction in Direction::ALL {
+            let mut next = board.clone();
+            if next.shift(direction).1 {
+                best = best.max(self.chance(&next, depth, probability));
+            }
+        }
+        best
+    }
+
+    /// The spawner places a tile on a random empty cell.
+    fn chance(&mut self, board: &G, depth: u32, probability: f64) -> f64 {
+        if depth == 0 || probability < self.expectimax.min_probability {
+            return self.expectimax.heuristic.evaluate(board);
+        }
+        let key = (board.clone(), depth);
+        if let Some(&value) = self.cache.get(&key) {
+            return value;
+        }
+
+        let cells = board.empty_cells();
+        let share = 1.0 / cells.len() as f64;
+        let mut total = 0.0;
+        for (r, c) in cells {
+            for &(val, odds) in self.odds {
+                let mut next = board.clone();
+                next.set(r, c, Some(val));
+                total += odds * self.max(&next, depth - 1, probability * odds * share);
+            }
+        }
+        let value = total * share;
+        self.cache.insert(key, value);
+        value
+    }
+}
@@ -0,0 +1,243 @@
+//! A 4x4 board packed into a `u64`, for fast simulation.
+//!
+//! Each cell is a nibble holding the tile's exponent (`0` for empty, `k` for `2^k`),
+//! with cell `(r, c)` at nibble `4 * r + c`. Every possible 16-bit row is slid once
+//! up front into lookup tables, so a whole move is four table lookups (plus two
+//! transposes for vertical moves).
+//!
+//! Tiles range from 2 to 32768. Two 32768 tiles do not merge, since the result would not
+//! fit in a nibble; otherwise moves behave exactly like [`Board::shift`]. So that the two
+//! always agree, [`BitBoard::from_board`] only packs boards whose tiles are smaller.
+
+use crate::{
+    board::{Board, Grid},
+    game::Direction,
+};
+use std::sync::OnceLock;
+
+/// The largest exponent a nibble can hold.
+const MAX_EXPONENT: u64 = 15;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct BitBoard(pub u64);
+
+/// Row lookup tables, indexed by the 16-bit row with its first cell in the low nibble.
+///
+/// Sliding either way merges the same pairs within each run of equal tiles, so one
+/// score table serves both directions.
+struct Tables {
+    left: Vec<u16>,
+    right: Vec<u16>,
+    score: Vec<u32>,
+}
+
+fn tables() -> &'static Tables {
+    static TABLES: OnceLock<Tables> = OnceLock::new();
+    TABLES.get_or_init(|| {
+        let mut tables = Tables {
+            left: vec![0; 1 << 16],
+            right: vec![0; 1 << 16],
+            score: vec![0; 1 << 16],
+        };
+        for row in 0..=u16::MAX {
+            let cells = unpack_row(row);
+            let (left, score) = slide_row(cells);
+            let mut reversed = cells;
+            reversed.reverse();
+            let (mut right, _) = slide_row(reversed);
+            right.reverse();
+            tables.left[row as usize] = pack_row(left);
+            tables.right[row as usize] = pack_row(right);
+            tables.score[row as usize] = score;
+        }
+        tables
+    })
+}
+
+fn unpack_row(row: u16) -> [u64; 4] {
+    [0, 1, 2, 3].map(|i| u64::from(row >> (4 * i)) & 0xf)
+}
+
+fn pack_row(cells: [u64; 4]) -> u16 {
+    cells
+        .iter()
+        .enumerate()
+        .fold(0, |row, (i, &exp)| row | (exp as u16) << (4 * i))
+}
+
+/// Slide a row of exponents towards index 0, returning it and the score gained.
+fn slide_row(cells: [u64; 4]) -> ([u64; 4], u32) {
+    let mut out = [0; 4];
+    let mut start = 0;
+    let mut merged = false;
+    let mut score = 0;
+    for exp in cells.into_iter().filter(|&exp| exp != 0) {
+        if start > 0 && out[start - 1] == exp && !merged && exp < MAX_EXPONENT {
+            out[start - 1] += 1;
+            score += 1 << out[start - 1];
+            merged = true;
+        } else {
+            out[start] = exp;
+            start += 1;
+            merged = false;
+        }
+    }
+    (out, score)
+}
+
+/// Swap rows and columns.
+fn transpose(x: u64) -> u64 {
+    let a1 = x & 0xF0F0_0F0F_F0F0_0F0F;
+    let a2 = x & 0x0000_F0F0_0000_F0F0;
+    let a3 = x & 0x0F0F_0000_0F0F_0000;
+    let a = a1 | (a2 << 12) | (a3 >> 12);
+    let b1 = a & 0xFF00_FF00_00FF_00FF;
+    let b2 = a & 0x00FF_00FF_0000_0000;
+    let b3 = a & 0x0000_0000_FF00_FF00;
+    b1 | (b2 >> 24) | (b3 << 24)
+}
+
+impl BitBoard {
+    /// Whether `val` can be stored: a power of two from 2 to 32768.
+    pub fn fits(val: u32) -> bool {
+        val.is_power_of_two() && (2..=1 << MAX_EXPONENT).contains(&val)
+    }
+
+    /// Whether `val` fits and so does the tile two of them merge into, so that it moves
+    /// exactly as it would on a [`Board`]: a power of two from 2 to 16384.
+    pub fn merges(val: u32) -> bool {
+        Self::fits(val) && val < 1 << MAX_EXPONENT
+    }
+
+    /// Pack a 4x4 board, or `None` if it has another size or a tile that
+    /// [does not merge](BitBoard::merges) as it would on the board.
+    pub fn from_board(board: &Board) -> Option<Self> {
+        if board.rows() != 4 || board.cols() != 4 {
+            return None;
+        }
+        let mut packed = 0;
+        for (i, val) in board.iter_rows().flatten().enumerate() {
+            if let Some(val) = *val {
+                if !Self::merges(val) {
+                    return None;
+                }
+                packed |= u64::from(val.trailing_zeros()) << (4 * i);
+            }
+        }
+        Some(Self(packed))
+    }
+
+    pub fn to_board(self) -> Board {
+        let mut board = Board::new(4, 4);
+        for r in 0..4 {
+            for c in 0..4 {
+                board.set(r, c, self.get(r, c));
+            }
+        }
+        board
+    }
+
+    fn exponent(self, r: usize, c: usize) -> u64 {
+        (self.0 >> (4 * (4 * r + c))) & 0xf
+    }
+
+    /// Apply a row table to each of the four rows.
+    fn map_rows(x: u64, table: &[u16]) -> u64 {
+        (0..4).fold(0, |out, r| {
+            let row = (x >> (16 * r)) & 0xffff;
+            out | u64::from(table[row as usize]) << (16 * r)
+        })
+    }
+
+    fn row_scores(x: u64) -> u32 {
+        let score = &tables().score;
+        (0..4)
+            .map(|r| score[((x >> (16 * r)) & 0xffff) as usize])
+            .sum()
+    }
+}
+
+impl Grid for BitBoard {
+    fn rows(&self) -> usize {
+        4
+    }
+
+    fn cols(&self) -> usize {
+        4
+    }
+
+    fn get(&self, r: usize, c: usize) -> Option<u32> {
+        match self.exponent(r, c) {
+            0 => None,
+            exp => Some(1 << exp),
+        }
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `val` is not a power of two from 2 to 32768.
+    fn set(&mut self, r: usize, c: usize, val: Option<u32>) {
+        let exp = match val {
+            Some(val) => {
+                assert!(Self::fits(val), "tile {val} does not fit in a bitboard");
+                u64::from(val.trailing_zeros())
+            }
+            None => 0,
+        };
+        let shift = 4 * (4 * r + c);
+        self.0 = (self.0 & !(0xf << shift)) | exp << shift;
+    }
+
+    fn empty_cells(&self) -> Vec<(usize, usize)> {
+        (0..16)
+            .filter(|i| (self.0 >> (4 * i)) & 0xf == 0)
+            .map(|i| (i / 4, i % 4))
+            .collect()
+    }
+
+    fn max_tile(&self) -> u32 {
+        match (0..16).map(|i| (self.0 >> (4 * i)) & 0xf).max() {
+            Some(exp) if exp > 0 => 1 << exp,
+            _ => 0,
+        }
+    }
+
+    fn is_stuck(&self) -> bool {
+        let full = (0..16).all(|i| (self.0 >> (4 * i)) & 0xf != 0);
+        full && Direction::ALL.into_iter().all(|direction| {
+            let mut probe = *self;
+            !probe.shift(direction).1
+        })
+    }
+
+    fn shift(&mut self, direction: Direction) -> (u32, bool) {
+        let tables = tables();
+        let before = self.0;
+        let (after, gained) = match direction {
+            Direction::Left => (
+                Self::map_rows(before, &tables.left),
+                Self::row_scores(before),
+            ),
+            Direction::Right => (
+                Self::map_rows(before, &tables.right),
+                Self::row_scores(before),
+            ),
+            Direction::Up => {
+                let t = transpose(before);
+                (
+                    transpose(Self::map_rows(t, &tables.left)),
+                    Self::row_scores(t),
+                )
+            }
+            Direction::Down => {
+                let t = transpose(before);
+                (
+                    transpose(Self::map_rows(t, &tables.right)),
+                    Self::row_scores(t),
+                )
+            }
+        };
+        self.0 = after;
+        (gained, after != before)
+    }
+}
@@ -0,0 +1,260 @@
+use crate::game::{Direction, Event};
+use std::{fmt, hash::Hash};
+
+/// The smallest number of rows or columns a board may have.
+pub const MIN_SIZE: usize = 2;
+/// The largest number of rows or columns a board may have.
+pub const MAX_SIZE: usize = 16;
+
+/// The operations shared by every board representation.
+///
+/// Search code written against this trait can run on the general [`Board`] or, for
+/// 4x4 games, on the much faster [`BitBoard`](crate::bitboard::BitBoard).
+pub trait Grid: Clone + Eq + Hash {
+    fn rows(&self) -> usize;
+    fn cols(&self) -> usize;
+    fn get(&self, r: usize, c: usize) -> Option<u32>;
+    fn set(&mut self, r: usize, c: usize, val: Option<u32>);
+    /// Coordinates of every empty cell, in reading order.
+    fn empty_cells(&self) -> Vec<(usize, usize)>;
+    /// The largest tile on the board, or 0 if it is empty.
+    fn max_tile(&self) -> u32;
+    /// Whether the board is full and no neighbours are equal, so no move is possible.
+    fn is_stuck(&self) -> bool;
+    /// Slide every tile towards `direction`, merging equal neighbours once per move.
+    ///
+    /// Returns the score gained from merges and whether the board changed.
+    fn shift(&mut self, direction: Direction) -> (u32, bool);
+}
+
+/// A rectangular grid of tiles.
+///
+/// The cells are enumerated like you would read a book: left to right, then top to bottom.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Board {
+    rows: usize,
+    cols: usize,
+    cells: Vec<Option<u32>>,
+}
+
+impl Board {
+    /// Create an empty board.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either dimension is outside `MIN_SIZE..=MAX_SIZE`.
+    pub fn new(rows: usize, cols: usize) -> Self {
+        assert!(
+            (MIN_SIZE..=MAX_SIZE).contains(&rows) && (MIN_SIZE..=MAX_SIZE).contains(&cols),
+            "board size {rows}x{cols} is outside {MIN_SIZE}..={MAX_SIZE}"
+        );
+        Self {
+            rows,
+            cols,
+            cells: vec![None; rows * cols],
+        }
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn get(&self, r: usize, c: usize) -> Option<u32> {
+        self.cells[r * self.cols + c]
+    }
+
+    pub fn set(&mut self, r: usize, c: usize, val: Option<u32>) {
+        self.cells[r * self.cols + c] = val;
+    }
+
+    pub fn row(&self, r: usize) -> &[Option<u32>] {
+        &self.cells[r * self.cols..(r + 1) * self.cols]
+    }
+
+    /// Iterate over the rows from top to bottom.
+    pub fn iter_rows(&self) -> impl Iterator<Item = &[Option<u32>]> {
+        self.cells.chunks(self.cols)
+    }
+
+    /// Coordinates of every empty cell, in reading order.
+    pub fn empty_cells(&self) -> Vec<(usize, usize)> {
+        (0..self.cells.len())
+            .filter(|&i| self.cells[i].is_none())
+            .map(|i| (i / self.cols, i % self.cols))
+            .collect()
+    }
+
+    /// The largest tile on the board, or 0 if it is empty.
+    pub fn max_tile(&self) -> u32 {
+        self.cells.iter().flatten().copied().max().unwrap_or(0)
+    }
+
+    /// Whether no move can change the board: it is full and no neighbours are equal.
+    pub fn is_stuck(&self) -> bool {
+        !(0..self.rows).any(|r| {
+            (0..self.cols).any(|c| {
+                let val = self.get(r, c);
+                let next_row = if r + 1 < self.rows {
+                    self.get(r + 1, c)
+                } else {
+                    None
+                };
+                let next_col = if c + 1 < self.cols {
+                    self.get(r, c + 1)
+                } else {
+                    None
+                };
+                val.is_none() || val == next_row || val == next_col
+            })
+        })
+    }
+
+    /// Slide every tile towards `direction`, merging equal neighbours once per move.
+    ///
+    /// Returns the score gained from merges and whether the board changed.
+    pub fn shift(&mut self, direction: Direction) -> (u32, bool) {
+        self.slide(direction, None)
+    }
+
+    /// Like [`Board::shift`], also appending an [`Event::Moved`] for every tile that
+    /// changes cell and an [`Event::Merged`] for every merge to `events`.
+    pub fn shift_events(&mut self, direction: Direction, events: &mut Vec<Event>) -> (u32, bool) {
+        self.slide(direction, Some(events))
+    }
+
+    fn slide(&mut self, direction: Direction, mut events: Option<&mut Vec<Event>>) -> (u32, bool) {
+        let coord = |i: usize| (i / self.cols, i % self.cols);
+        let mut gained: u32 = 0;
+        let mut changed = false;
+        for line in 0..self.line_count(direction) {
+            let mut start: usize = 0;
+            let mut merged = false;
+            for i in 0..self.line_len(direction) {
+                let from = self.line_index(direction, line, i);
+                if let Some(val) = self.cells[from].take() {
+                    let prev = if start == 0 {
+                        None
+                    } else {
+                        Some(self.line_index(direction, line, start - 1))
+                    };
+                    match prev {
+                        Some(prev) if Some(val) == self.cells[prev] && !merged => {
+                            let new_val = val + val;
+                            self.cells[prev] = Some(new_val);
+                            gained += new_val;
+                            merged = true;
+                            changed = true;
+                            if let Some(events) = events.as_deref_mut() {
+                                let at = coord(prev);
+                                events.push(Event::Moved {
+                                    from: coord(from),
+                                    to: at,
+                                    value: val,
+                                });
+                                events.push(Event::Merged { at, value: new_val });
+                            }
+                        }
+                        _ => {
+                            let to = self.line_index(direction, line, start);
+                            if start != i {
+                                changed = true;
+                                if let Some(events) = events.as_deref_mut() {
+                                    events.push(Event::Moved {
+                                        from: coord(from),
+                                        to: coord(to),
+                                        value: val,
+                                    });
+                                }
+                            }
+                            self.cells[to] = Some(val);
+                            start += 1;
+                            merged = false;
+                        }
+                    }
+                }
+            }
+        }
+        (gained, changed)
+    }
+
+    /// Number of independent lines a move in `direction` slides along.
+    fn line_count(&self, direction: Direction) -> usize {
+        match direction {
+            Direction::Left | Direction::Right => self.rows,
+            Direction::Up | Direction::Down => self.cols,
+        }
+    }
+
+    fn line_len(&self, direction: Direction) -> usize {
+        match direction {
+            Direction::Left | Direction::Right => self.cols,
+            Direction::Up | Direction::Down => self.rows,
+        }
+    }
+
+    /// Cell index of the `i`-th tile of `line`, counting from the edge tiles slide towards.
+    fn line_index(&self, direction: Direction, line: usize, i: usize) -> usize {
+        match direction {
+            Direction::Left => line * self.cols + i,
+            Direction::Right => line * self.cols + (self.cols - 1 - i),
+            Direction::Up => i * self.cols + line,
+            Direction::Down => (self.rows - 1 - i) * self.cols + line,
+        }
+    }
+}
+
+/// One line per row, with the values right-aligned and empty cells shown as `.`.
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self.max_tile().to_string().len();
+        for row in self.iter_rows() {
+            let cells: Vec<String> = row
+                .iter()
+                .map(|val| match val {
+                    Some(val) => format!("{val:>width$}"),
+                    None => format!("{:>width$}", "."),
+                })
+                .collect();
+            writeln!(f, "{}", cells.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+impl Grid for Board {
+    fn rows(&self) -> usize {
+        Board::rows(self)
+    }
+
+    fn cols(&self) -> usize {
+        Board::cols(self)
+    }
+
+    fn get(&self, r: usize, c: usize) -> Option<u32> {
+        Board::get(self, r, c)
+    }
+
+    fn set(&mut self, r: usize, c: usize, val: Option<u32>) {
+        Board::set(self, r, c, val)
+    }
+
+    fn empty_cells(&self) -> Vec<(usize, usize)> {
+        Board::empty_cells(self)
+    }
+
+    fn max_tile(&self) -> u32 {
+        Board::max_tile(self)
+    }
+
+    fn is_stuck(&self) -> bool {
+        Board::is_stuck(self)
+    }
+
+    fn shift(&mut self, direction: Direction) -> (u32, bool) {
+        Board::shift(self, direction)
+    }
+}
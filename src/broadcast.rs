@@ -0,0 +1,194 @@
+//! Letting other terminals watch a game as it is played.
+//!
+//! A [`Broadcaster`] listens on a TCP address and sends every spectator the game's
+//! [`State`] as a line of JSON whenever it changes. Spectators may connect at any time:
+//! each starts with the latest state. A [`Spectator`] is the other end.
+
+use crate::{game::Game, server::State};
+use std::{
+    io::{self, prelude::*, BufReader},
+    net::{Ipv4Addr, Ipv6Addr, Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    sync::{
+        mpsc::{self, Receiver, TryRecvError},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+/// How long a spectator may hold up a write before it is dropped, so that a slow one
+/// never stalls the game.
+const WRITE_TIMEOUT: Duration = Duration::from_millis(250);
+
+#[derive(Default)]
+struct Audience {
+    spectators: Vec<TcpStream>,
+    /// The last line sent, for spectators who join later.
+    last: Option<String>,
+    /// Set when the broadcast ends, turning away anyone who connects after.
+    closed: bool,
+}
+
+/// Sends a game's state to everyone watching.
+pub struct Broadcaster {
+    audience: Arc<Mutex<Audience>>,
+    /// Where spectators connect, to wake the accepting thread when the broadcast ends.
+    addr: Option<SocketAddr>,
+    accepting: Option<JoinHandle<()>>,
+}
+
+impl Broadcaster {
+    /// Start accepting spectators on `listener`, which is closed when the broadcaster is
+    /// dropped.
+    pub fn new(listener: TcpListener) -> Self {
+        let audience = Arc::new(Mutex::new(Audience::default()));
+        let shared = Arc::clone(&audience);
+        let addr = listener.local_addr().ok().map(|mut addr| {
+            if addr.ip().is_unspecified() {
+                addr.set_ip(match addr {
+                    SocketAddr::V4(_) => Ipv4Addr::LOCALHOST.into(),
+                    SocketAddr::V6(_) => Ipv6Addr::LOCALHOST.into(),
+                });
+            }
+            addr
+        });
+        let accepting = thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else {
+                    continue;
+                };
+                if stream.set_write_timeout(Some(WRITE_TIMEOUT)).is_err() {
+                    continue;
+                }
+                // Greet the spectator with the latest state without holding the lock, so
+                // that a slow one never holds up `publish`, and go again if the game moved
+                // on in the meantime.
+                let mut sent = None;
+                loop {
+                    let mut audience = shared.lock().unwrap();
+                    if audience.closed {
+                        return;
+                    }
+                    if audience.last == sent {
+                        audience.spectators.push(stream);
+                        break;
+                    }
+                    let line = audience.last.clone();
+                    drop(audience);
+                    if let Some(line) = &line {
+                        if writeln!(stream, "{line}").is_err() {
+                            break;
+                        }
+                    }
+                    sent = line;
+                }
+            }
+        });
+        Self {
+            audience,
+            addr,
+            accepting: Some(accepting),
+        }
+    }
+
+    /// Send the game's state to every spectator, unless it has not changed since the
+    /// last call. Spectators that cannot keep up or have gone are dropped.
+    pub fn publish(&self, game: &Game) {
+        let line = serde_json::to_string(&State::of(game)).unwrap();
+        let mut audience = self.audience.lock().unwrap();
+        if audience.last.as_ref() == Some(&line) {
+            return;
+        }
+        audience
+            .spectators
+            .retain_mut(|stream| writeln!(stream, "{line}").is_ok());
+        audience.last = Some(line);
+    }
+
+    /// The number of spectators watching, as far as is known: one that has gone is only
+    /// noticed when next written to.
+    pub fn spectators(&self) -> usize {
+        self.audience.lock().unwrap().spectators.len()
+    }
+}
+
+impl Drop for Broadcaster {
+    /// Hang up on every spectator, which tells them the game is over, and stop
+    /// listening.
+    fn drop(&mut self) {
+        let mut audience = self.audience.lock().unwrap();
+        audience.closed = true;
+        for stream in audience.spectators.drain(..) {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+        drop(audience);
+        // The accepting thread only notices the end once someone connects.
+        let woken = self
+            .addr
+            .is_some_and(|addr| TcpStream::connect(addr).is_ok());
+        if let Some(accepting) = self.accepting.take().filter(|_| woken) {
+            let _ = accepting.join();
+        }
+    }
+}
+
+/// A read-only view of a game being broadcast.
+pub struct Spectator {
+    stream: TcpStream,
+    incoming: Receiver<State>,
+    over: bool,
+}
+
+impl Spectator {
+    /// Start watching the game broadcast at `addr`.
+    pub fn connect<A: ToSocketAddrs>(addr: A) -> io::Result<Spectator> {
+        let stream = TcpStream::connect(addr)?;
+        let reader = BufReader::new(stream.try_clone()?);
+        let (sender, incoming) = mpsc::channel();
+        // Anything unreadable ends the broadcast, as does the game's end.
+        thread::spawn(move || {
+            for line in reader.lines() {
+                let Some(state) = line.ok().and_then(|line| serde_json::from_str(&line).ok())
+                else {
+                    return;
+                };
+                if sender.send(state).is_err() {
+                    return;
+                }
+            }
+        });
+        Ok(Spectator {
+            stream,
+            incoming,
+            over: false,
+        })
+    }
+
+    /// The latest state received since the last call, skipping any older ones, or
+    /// `None` if nothing new has arrived.
+    pub fn latest(&mut self) -> Option<State> {
+        let mut latest = None;
+        loop {
+            match self.incoming.try_recv() {
+                Ok(state) => latest = Some(state),
+                Err(TryRecvError::Empty) => return latest,
+                Err(TryRecvError::Disconnected) => {
+                    self.over = true;
+                    return latest;
+                }
+            }
+        }
+    }
+
+    /// Whether the broadcast has ended, because the game did or the connection was
+    /// lost. Only known once [`Spectator::latest`] has read everything before the end.
+    pub fn is_over(&self) -> bool {
+        self.over
+    }
+}
+
+impl Drop for Spectator {
+    fn drop(&mut self) {
+        let _ = self.stream.shutdown(Shutdown::Both);
+    }
+}
@@ -0,0 +1,103 @@
+//! The optional settings file, in a small INI dialect:
+//!
+//! ```text
+//! # Comments start with `#`.
+//! [keys]
+//! undo = u Backspace
+//! quit = q Esc
+//! ```
+//!
+//! Each front end feature reads its own section and reports unknown names itself.
+
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+/// One `name = value` line.
+pub struct Setting {
+    pub section: String,
+    pub name: String,
+    pub value: String,
+    /// 1-based line number, for error messages.
+    pub line: usize,
+}
+
+#[derive(Default)]
+pub struct Config {
+    path: PathBuf,
+    settings: Vec<Setting>,
+}
+
+impl Config {
+    /// `rust_2048/config` under the XDG config directory, or `None` if neither that nor
+    /// `$HOME` is set.
+    pub fn default_path() -> Option<PathBuf> {
+        let dir = env::var_os("XDG_CONFIG_HOME")
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+        Some(dir.join("rust_2048").join("config"))
+    }
+
+    /// Read the file at `path`; a missing file is an empty config.
+    pub fn load(path: &Path) -> Result<Config, String> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(format!("could not read {}: {err}", path.display())),
+        };
+        Config::parse(path, &text)
+    }
+
+    /// Parse the text of a config file read from `path`.
+    pub fn parse(path: &Path, text: &str) -> Result<Config, String> {
+        let mut config = Config {
+            path: path.to_path_buf(),
+            settings: Vec::new(),
+        };
+        let mut section = String::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(name) = line
+                .strip_prefix('[')
+                .and_then(|rest| rest.strip_suffix(']'))
+            {
+                section = name.trim().to_string();
+                continue;
+            }
+            let setting = line.split_once('=').map(|(name, value)| Setting {
+                section: section.clone(),
+                name: name.trim().to_string(),
+                value: value.trim().to_string(),
+                line: i + 1,
+            });
+            match setting {
+                Some(setting) if !setting.name.is_empty() => config.settings.push(setting),
+                _ => {
+                    return Err(format!(
+                        "{}:{}: expected `[section]` or `name = value`",
+                        path.display(),
+                        i + 1
+                    ))
+                }
+            }
+        }
+        Ok(config)
+    }
+
+    /// The settings under `[name]`, in file order.
+    pub fn section<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Setting> {
+        self.settings
+            .iter()
+            .filter(move |setting| setting.section == name)
+    }
+
+    /// An error message pointing at the line `setting` came from.
+    pub fn error(&self, setting: &Setting, reason: &str) -> String {
+        format!("{}:{}: {reason}", self.path.display(), setting.line)
+    }
+}
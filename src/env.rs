@@ -0,0 +1,313 @@
+//! A reinforcement-learning environment over [`Game`], in the style of Gym.
+//!
+//! [`Env::reset`] starts an episode from a seed and returns the first observation;
+//! [`Env::step`] plays one action and returns the next observation, the points scored
+//! as the reward, whether the episode is over and an [`Info`] with the details. Actions
+//! are [`Direction`]s, and [`Env::action_mask`] tells which of them would move anything,
+//! in the order of [`Direction::ALL`], so a policy picking by index can play
+//! `Direction::ALL[i]`.
+//!
+//! Observations are flat `f32` vectors in row-major order, laid out as described by
+//! [`Env::observation_shape`] and encoded as chosen with [`Encoding`]. [`VecEnv`] runs
+//! many environments in lockstep, one action each per call.
+
+use crate::game::{Direction, Game, GameConfig, GameStatus};
+use std::thread;
+
+/// How the board is turned into numbers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    /// Each cell's value, 0 when empty. Shape `[rows, cols]`.
+    Raw,
+    /// Each cell's log2, so 2 is 1 and 2048 is 11, and 0 when empty. Shape
+    /// `[rows, cols]`.
+    Log2,
+    /// One plane per log2 value, holding 1 where a cell has that value and 0 elsewhere.
+    /// Plane 0 marks the empty cells and the last plane also takes every bigger tile.
+    /// Shape `[planes, rows, cols]`.
+    OneHot { planes: usize },
+}
+
+/// The details of a step beyond the observation, reward and end of the episode.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Info {
+    /// Whether the action changed the board. Actions that do not are not played.
+    pub moved: bool,
+    pub score: u32,
+    pub max_tile: u32,
+    pub moves: u32,
+    pub status: GameStatus,
+    /// The actions that are legal from the new position.
+    pub action_mask: [bool; 4],
+}
+
+/// The outcome of [`Env::step`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Step {
+    pub observation: Vec<f32>,
+    /// Points scored by the merges in the move.
+    pub reward: f32,
+    /// The game was won or lost; call [`Env::reset`] before stepping again.
+    pub done: bool,
+    pub info: Info,
+}
+
+/// A single game played one action at a time.
+#[derive(Clone, Debug)]
+pub struct Env {
+    pub(crate) game: Game,
+    encoding: Encoding,
+}
+
+impl Env {
+    /// An environment playing games set up by `config`, whose seed is ignored in favour
+    /// of the one given to [`Env::reset`]. Undo history is not kept.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `config` is invalid, as [`Game::with_config`] does, if it spawns 1s,
+    /// whose log2 of 0 the encodings keep for empty cells, or if `encoding` has no
+    /// planes.
+    pub fn new(config: GameConfig, encoding: Encoding) -> Self {
+        assert!(
+            config.spawn.weights.iter().all(|&(val, _)| val >= 2),
+            "the encodings cannot tell 1s from empty cells"
+        );
+        assert!(
+            encoding != Encoding::OneHot { planes: 0 },
+            "one-hot encoding needs at least one plane"
+        );
+        let game = Game::with_config(GameConfig {
+            seed: Some(0),
+            undo_limit: Some(0),
+            ..config
+        });
+        Self { game, encoding }
+    }
+
+    /// Start a new episode from `seed` and return its first observation.
+    pub fn reset(&mut self, seed: u64) -> Vec<f32> {
+        self.game.reseed(seed);
+        self.observation()
+    }
+
+    /// Play `action`. An action that moves nothing leaves the game as it was and earns
+    /// no reward.
+    pub fn step(&mut self, action: Direction) -> Step {
+        let before = self.game.score();
+        let (status, events) = self.game.move_events(action);
+        Step {
+            observation: self.observation(),
+            reward: (self.game.score() - before) as f32,
+            done: status != GameStatus::Continue,
+            info: self.info(!events.is_empty()),
+        }
+    }
+
+    fn info(&self, moved: bool) -> Info {
+        let result = self.game.result();
+        Info {
+            moved,
+            score: result.score,
+            max_tile: result.max_tile,
+            moves: result.moves,
+            status: self.game.status(),
+            action_mask: self.action_mask(),
+        }
+    }
+
+    /// Which of [`Direction::ALL`] would change the board.
+    pub fn action_mask(&self) -> [bool; 4] {
+        Direction::ALL.map(|direction| {
+            self.game.status() == GameStatus::Continue && self.game.is_legal(direction)
+        })
+    }
+
+    /// The dimensions of an observation, outermost first.
+    pub fn observation_shape(&self) -> Vec<usize> {
+        let (rows, cols) = (self.game.board().rows(), self.game.board().cols());
+        match self.encoding {
+            Encoding::Raw | Encoding::Log2 => vec![rows, cols],
+            Encoding::OneHot { planes } => vec![planes, rows, cols],
+        }
+    }
+
+    /// The current position, encoded.
+    pub fn observation(&self) -> Vec<f32> {
+        let cells = self.game.board().iter_rows().flatten();
+        let log2 = |val: &Option<u32>| val.map_or(0, |val| val.ilog2() as usize);
+        match self.encoding {
+            Encoding::Raw => cells.map(|val| val.unwrap_or(0) as f32).collect(),
+            Encoding::Log2 => cells.map(|val| log2(val) as f32).collect(),
+            Encoding::OneHot { planes } => {
+                let len = self.game.board().rows() * self.game.board().cols();
+                let mut observation = vec![0.0; planes * len];
+                for (i, val) in cells.enumerate() {
+                    observation[log2(val).min(planes - 1) * len + i] = 1.0;
+                }
+                observation
+            }
+        }
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+}
+
+/// The outcome of [`VecEnv::step`], one entry per environment.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VecStep {
+    /// Every environment's observation, one after another.
+    pub observations: Vec<f32>,
+    pub rewards: Vec<f32>,
+    pub dones: Vec<bool>,
+    pub infos: Vec<Info>,
+}
+
+/// Many environments stepped together.
+///
+/// Finished environments stay finished, ignoring their actions, until
+/// [`VecEnv::reset_done`] starts them again.
+#[derive(Clone, Debug)]
+pub struct VecEnv {
+    envs: Vec<Env>,
+    done: Vec<bool>,
+    /// Threads to spread each step over.
+    threads: usize,
+}
+
+impl VecEnv {
+    /// `n` environments playing games set up by `config`, stepped on one thread.
+    pub fn new(n: usize, config: GameConfig, encoding: Encoding) -> Self {
+        Self {
+            envs: vec![Env::new(config, encoding); n],
+            done: vec![false; n],
+            threads: 1,
+        }
+    }
+
+    /// Spread each step over up to `threads` threads, which pays off when steps are
+    /// slow, as with an evil spawner.
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.envs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.envs.is_empty()
+    }
+
+    pub fn envs(&self) -> &[Env] {
+        &self.envs
+    }
+
+    /// Start every environment on a new episode, the `i`th from `seeds[i]`, and return
+    /// all the first observations.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is not one seed per environment.
+    pub fn reset(&mut self, seeds: &[u64]) -> Vec<f32> {
+        assert_eq!(seeds.len(), self.envs.len(), "one seed per environment");
+        for (env, &seed) in self.envs.iter_mut().zip(seeds) {
+            env.reset(seed);
+        }
+        self.done.fill(false);
+        self.observations()
+    }
+
+    /// Start the finished environments again, the `i`th from `seeds[i]`, and return all
+    /// the observations. The others carry on and ignore their seeds.
+    pub fn reset_done(&mut self, seeds: &[u64]) -> Vec<f32> {
+        assert_eq!(seeds.len(), self.envs.len(), "one seed per environment");
+        for ((env, done), &seed) in self.envs.iter_mut().zip(&mut self.done).zip(seeds) {
+            if *done {
+                env.reset(seed);
+                *done = false;
+            }
+        }
+        self.observations()
+    }
+
+    /// Play `actions[i]` in the `i`th environment.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is not one action per environment.
+    pub fn step(&mut self, actions: &[Direction]) -> VecStep {
+        assert_eq!(actions.len(), self.envs.len(), "one action per environment");
+        let chunk = self.envs.len().div_ceil(self.threads).max(1);
+        let step = |(env, done): (&mut Env, &mut bool), action: Direction| {
+            let step = if *done {
+                Step {
+                    observation: env.observation(),
+                    reward: 0.0,
+                    done: true,
+                    info: env.info(false),
+                }
+            } else {
+                env.step(action)
+            };
+            *done = step.done;
+            step
+        };
+        let steps: Vec<Step> = if self.threads == 1 {
+            self.envs
+                .iter_mut()
+                .zip(&mut self.done)
+                .zip(actions)
+                .map(|(env, &action)| step(env, action))
+                .collect()
+        } else {
+            thread::scope(|scope| {
+                let handles: Vec<_> = self
+                    .envs
+                    .chunks_mut(chunk)
+                    .zip(self.done.chunks_mut(chunk))
+                    .zip(actions.chunks(chunk))
+                    .map(|((envs, done), actions)| {
+                        scope.spawn(move || {
+                            envs.iter_mut()
+                                .zip(done)
+                                .zip(actions)
+                                .map(|(env, &action)| step(env, action))
+                                .collect::<Vec<_>>()
+                        })
+                    })
+                    .collect();
+                handles
+                    .into_iter()
+                    .flat_map(|handle| handle.join().unwrap())
+                    .collect()
+            })
+        };
+
+        let mut batch = VecStep {
+            observations: Vec::new(),
+            rewards: Vec::with_capacity(steps.len()),
+            dones: Vec::with_capacity(steps.len()),
+            infos: Vec::with_capacity(steps.len()),
+        };
+        for step in steps {
+            batch.observations.extend(step.observation);
+            batch.rewards.push(step.reward);
+            batch.dones.push(step.done);
+            batch.infos.push(step.info);
+        }
+        batch
+    }
+
+    /// Which actions are legal in each environment.
+    pub fn action_masks(&self) -> Vec<[bool; 4]> {
+        self.envs.iter().map(Env::action_mask).collect()
+    }
+
+    fn observations(&self) -> Vec<f32> {
+        self.envs.iter().flat_map(Env::observation).collect()
+    }
+}
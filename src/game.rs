@@ -1,313 +1,465 @@
-use rand::{rngs::ThreadRng, Rng};
-use std::io::{self, prelude::*};
-use termion::{clear, color, cursor, event::Key::Char, input::TermRead, raw::IntoRawMode, style};
+use crate::{ai, board::Board};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
 
+/// How the spawner places new tiles.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpawnPolicy {
+    /// Each value the spawner can place with its weight. Weights are relative, so
+    /// `[(2, 9.0), (4, 1.0)]` is the same as `[(2, 0.9), (4, 0.1)]`.
+    pub weights: Vec<(u32, f64)>,
+    /// Tiles placed after every move that changes the board, while there is room.
+    pub per_move: u32,
+    /// Tiles placed when a game starts.
+    pub start: u32,
+    pub spawner: Spawner,
+}
+
+/// Who decides where new tiles go.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Spawner {
+    /// A random empty cell, with a value drawn by weight.
+    #[default]
+    Random,
+    /// An adversary that picks the cell and the value, out of those listed in the
+    /// weights, that leave the player worst off after `depth` replies, or fewer while
+    /// the board is open enough to make that slow; see [`ai::worst_spawn`]. It ignores
+    /// how the values are weighted, and the seed.
+    Evil { depth: u32 },
+}
+
+impl Default for SpawnPolicy {
+    /// The original game's: a 2 nine times in ten and a 4 otherwise, one tile per move
+    /// and two to start.
+    fn default() -> Self {
+        Self {
+            weights: vec![(2, 0.9), (4, 0.1)],
+            per_move: 1,
+            start: 2,
+            spawner: Spawner::Random,
+        }
+    }
+}
+
+impl SpawnPolicy {
+    /// The policy of games saved before it could be configured, which spawned a 4 three
+    /// times in ten.
+    pub(crate) fn legacy() -> Self {
+        Self {
+            weights: vec![(2, 0.7), (4, 0.3)],
+            ..Self::default()
+        }
+    }
+
+    /// Parse weights written as `value:weight` pairs separated by commas or spaces,
+    /// such as `2:0.9,4:0.1`. The result still needs [`SpawnPolicy::validate`].
+    pub fn parse_weights(text: &str) -> Result<Vec<(u32, f64)>, String> {
+        text.split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (value, weight) = pair
+                    .split_once(':')
+                    .ok_or_else(|| format!("expected `value:weight`, found `{pair}`"))?;
+                let value = value
+                    .parse()
+                    .map_err(|_| format!("invalid tile value `{value}`"))?;
+                let weight = weight
+                    .parse()
+                    .map_err(|_| format!("invalid weight `{weight}`"))?;
+                Ok((value, weight))
+            })
+            .collect()
+    }
+
+    /// Check that the spawner can follow this policy: some value to place, every value a
+    /// power of two listed once with a positive weight, and at least one tile per move
+    /// and at the start.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.weights.is_empty() {
+            return Err("no tile values to spawn".to_string());
+        }
+        for (i, &(value, weight)) in self.weights.iter().enumerate() {
+            if !value.is_power_of_two() {
+                return Err(format!("spawned tile {value} is not a power of two"));
+            }
+            if !weight.is_finite() || weight <= 0.0 {
+                return Err(format!("weight of tile {value} must be positive"));
+            }
+            if self.weights[..i].iter().any(|&(other, _)| other == value) {
+                return Err(format!("tile {value} is listed twice"));
+            }
+        }
+        if self.per_move == 0 || self.start == 0 {
+            return Err("at least one tile must spawn per move and at the start".to_string());
+        }
+        if self.spawner == (Spawner::Evil { depth: 0 }) {
+            return Err("the evil spawner must search at least one move".to_string());
+        }
+        Ok(())
+    }
+
+    /// Each value with its probability.
+    pub fn odds(&self) -> Vec<(u32, f64)> {
+        let total: f64 = self.weights.iter().map(|&(_, weight)| weight).sum();
+        self.weights
+            .iter()
+            .map(|&(value, weight)| (value, weight / total))
+            .collect()
+    }
+}
+
+/// Options fixed when a game is created.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameConfig {
+    pub rows: usize,
+    pub cols: usize,
+    /// Seed for the tile spawner; a random one is picked when `None`.
+    pub seed: Option<u64>,
+    /// How many moves may be taken back in one game; `None` means no limit.
+    pub undo_limit: Option<u32>,
+    /// The tile that wins the game.
+    pub target: u32,
+    pub spawn: SpawnPolicy,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self {
+            rows: 4,
+            cols: 4,
+            seed: None,
+            undo_limit: None,
+            target: 2048,
+            spawn: SpawnPolicy::default(),
+        }
+    }
+}
+
+/// A single game of 2048.
+///
+/// Every spawn is drawn from an RNG seeded with [`Game::seed`], so the same seed and
+/// the same sequence of moves always produce the same game.
+#[derive(Clone, Debug)]
 pub struct Game {
-    pub(crate) board: [[Option<u32>; 4]; 4],
-    score: u32,
-    rng: ThreadRng,
+    pub(crate) board: Board,
+    pub(crate) score: u32,
+    pub(crate) status: GameStatus,
+    pub(crate) moves: u32,
+    pub(crate) seed: u64,
+    pub(crate) rng: ChaCha8Rng,
+    pub(crate) undo_limit: Option<u32>,
+    pub(crate) target: u32,
+    pub(crate) spawn: SpawnPolicy,
+    /// Set once the player chooses to play on after winning.
+    pub(crate) keep_going: bool,
+    /// Number of undos taken so far.
+    pub(crate) undos: u32,
+    pub(crate) undo_stack: Vec<Snapshot>,
+    pub(crate) redo_stack: Vec<Snapshot>,
+}
+
+/// Everything a move can change, so that it can be taken back exactly.
+#[derive(Clone, Debug)]
+pub(crate) struct Snapshot {
+    pub(crate) board: Board,
+    pub(crate) score: u32,
+    pub(crate) status: GameStatus,
+    pub(crate) moves: u32,
+    pub(crate) rng: ChaCha8Rng,
+}
+
+/// The outcome of a finished (or abandoned) game.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GameResult {
+    pub score: u32,
+    pub max_tile: u32,
+    pub moves: u32,
+    /// Whether any move was taken back, so such scores can be ranked separately.
+    pub undo_used: bool,
 }
 
 impl Game {
     pub fn new() -> Self {
+        Self::with_config(GameConfig::default())
+    }
+
+    /// Create a game with an empty board of the configured size.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the board size is outside `board::MIN_SIZE..=board::MAX_SIZE` or the
+    /// spawn policy is invalid, as told by [`SpawnPolicy::validate`].
+    pub fn with_config(config: GameConfig) -> Self {
+        if let Err(err) = config.spawn.validate() {
+            panic!("invalid spawn policy: {err}");
+        }
+        let seed = config.seed.unwrap_or_else(|| rand::thread_rng().gen());
         Self {
             score: 0,
-            board: [[None; 4]; 4],
-            rng: rand::thread_rng(),
+            board: Board::new(config.rows, config.cols),
+            status: GameStatus::Continue,
+            moves: 0,
+            seed,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            undo_limit: config.undo_limit,
+            target: config.target,
+            spawn: config.spawn,
+            keep_going: false,
+            undos: 0,
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
         }
     }
 
-    pub fn start(&mut self) -> io::Result<()> {
-        let stdout = io::stdout().lock();
-        let mut stdout = stdout.into_raw_mode()?;
-        let stdin = io::stdin().lock();
-        let mut stdin_keys = stdin.keys();
-
-        for _ in 0..2 {
+    /// Clear the board and score, restart the spawner from the seed, then place the
+    /// starting tiles.
+    pub fn reset(&mut self) {
+        self.rng = ChaCha8Rng::seed_from_u64(self.seed);
+        self.board = Board::new(self.board.rows(), self.board.cols());
+        self.score = 0;
+        self.status = GameStatus::Continue;
+        self.keep_going = false;
+        self.moves = 0;
+        self.undos = 0;
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        for _ in 0..self.spawn.start {
             self.set_rand();
         }
+    }
 
-        write!(stdout, "{}", clear::All)?;
-        write!(stdout, "{}", cursor::Goto(1, 1))?;
-        stdout.write_all(self.board_to_string(None).as_bytes())?;
-        stdout.write_all(
-            format!("\n{}Score: {}{}\n\r", style::Bold, self.score, style::Reset).as_bytes(),
-        )?;
-        stdout.flush().unwrap();
-
-        loop {
-            let op = stdin_keys.next().unwrap().unwrap();
-            let (status, coord) = match op {
-                Char('h') => self.move_board(Direction::Left),
-                Char('j') => self.move_board(Direction::Down),
-                Char('k') => self.move_board(Direction::Up),
-                Char('l') => self.move_board(Direction::Right),
-                Char('q') => break,
-                _ => (GameStatus::Continue, None),
-            };
-            match status {
-                GameStatus::Lost => {
-                    // TODO: game is terminated
-                    break;
-                }
-                GameStatus::Win | GameStatus::Continue if coord.is_some() => {
-                    write!(stdout, "{}", cursor::Goto(1, 1))?;
-                    stdout.write_all(self.board_to_string(coord).as_bytes())?;
-                    stdout.write_all(
-                        format!("\n{}Score: {}{}\n\r", style::Bold, self.score, style::Reset)
-                            .as_bytes(),
-                    )?;
-                    stdout.flush()?;
-                    if status == GameStatus::Win {
-                        break;
-                    }
-                }
-                _ => {}
+    /// Switch to a new seed and start over, as if the game had been created with it.
+    pub fn reseed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.reset();
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn score(&self) -> u32 {
+        self.score
+    }
+
+    pub fn status(&self) -> GameStatus {
+        self.status
+    }
+
+    pub fn target(&self) -> u32 {
+        self.target
+    }
+
+    pub fn spawn_policy(&self) -> &SpawnPolicy {
+        &self.spawn
+    }
+
+    /// Play on after a win; the game will not report [`GameStatus::Win`] again.
+    pub fn keep_going(&mut self) {
+        self.keep_going = true;
+        if self.status == GameStatus::Win {
+            // The winning move may also have left no moves.
+            self.status = if self.board.is_stuck() {
+                GameStatus::Lost
+            } else {
+                GameStatus::Continue
             };
         }
+    }
 
-        write!(stdout, "{}", style::Reset)?;
-        stdout.flush()?;
-        Ok(())
+    /// Whether the player chose to play on past the target tile.
+    pub fn is_endless(&self) -> bool {
+        self.keep_going
     }
 
-    fn set_rand(&mut self) -> Option<(usize, usize)> {
-        let available: Vec<usize> = (0..16)
-            .filter(|i| self.board[i / 4][i % 4].is_none())
-            .collect();
-        let len = available.len();
-        if len == 0 {
-            return None;
+    /// Number of moves that changed the board, not counting those taken back.
+    pub fn moves(&self) -> u32 {
+        self.moves
+    }
+
+    pub fn undo_used(&self) -> bool {
+        self.undos > 0
+    }
+
+    /// How many more undos are allowed, or `None` if there is no limit.
+    pub fn undos_left(&self) -> Option<u32> {
+        self.undo_limit
+            .map(|limit| limit.saturating_sub(self.undos))
+    }
+
+    pub fn result(&self) -> GameResult {
+        GameResult {
+            score: self.score,
+            max_tile: self.board.max_tile(),
+            moves: self.moves,
+            undo_used: self.undo_used(),
         }
-        let index = available[self.rng.gen_range(0..len)];
-        let new_val = if self.rng.gen::<f64>() > 0.7 { 4 } else { 2 };
-        let (r, c) = (index / 4, index % 4);
-        self.board[r][c] = Some(new_val);
-        Some((r, c))
     }
 
-    pub fn move_board(&mut self, direction: Direction) -> (GameStatus, Option<(usize, usize)>) {
-        let (max_tile, changed) = match direction {
-            Direction::Up => self.move_up(),
-            Direction::Down => self.move_down(),
-            Direction::Left => self.move_left(),
-            Direction::Right => self.move_right(),
-        };
-        if max_tile == 2048 {
-            return (GameStatus::Win, None);
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty() && self.undos_left() != Some(0)
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    /// Take back the last move, restoring the board, score and spawner exactly.
+    ///
+    /// Returns `false` if there is nothing to undo or the undo limit has been reached.
+    pub fn undo(&mut self) -> bool {
+        if !self.can_undo() {
+            return false;
         }
-        if self.check_is_end() {
-            return (GameStatus::Lost, None);
+        let snapshot = self.undo_stack.pop().unwrap();
+        let current = self.restore(snapshot);
+        self.redo_stack.push(current);
+        self.undos += 1;
+        true
+    }
+
+    /// Replay the last move taken back by [`Game::undo`].
+    ///
+    /// Returns `false` if there is nothing to redo.
+    pub fn redo(&mut self) -> bool {
+        match self.redo_stack.pop() {
+            Some(snapshot) => {
+                let current = self.restore(snapshot);
+                self.undo_stack.push(current);
+                true
+            }
+            None => false,
         }
-        if changed {
-            return (GameStatus::Continue, self.set_rand());
+    }
+
+    pub(crate) fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            board: self.board.clone(),
+            score: self.score,
+            status: self.status,
+            moves: self.moves,
+            rng: self.rng.clone(),
         }
-        (GameStatus::Continue, None)
-    }
-
-    pub fn board_to_string(&self, coord: Option<(usize, usize)>) -> String {
-        // game board display:
-        // ┌──────┬──────┬──────┬──────┐
-        // │      │      │      │      │
-        // │    2 │ 1024 │    8 │   16 │
-        // │      │      │      │      │
-        // ├──────┼──────┼──────┼──────┤
-        // │      │      │      │      │
-        // │    2 │ 1024 │    8 │   16 │
-        // │      │      │      │      │
-        // ├──────┼──────┼──────┼──────┤
-        // │      │      │      │      │
-        // │    2 │ 1024 │    8 │   16 │
-        // │      │      │      │      │
-        // ├──────┼──────┼──────┼──────┤
-        // │      │      │      │      │
-        // │    2 │ 1024 │    8 │   16 │
-        // │      │      │      │      │
-        // └──────┴──────┴──────┴──────┘
-        // height 17; width 31 (29 characters + \n\r)
-
-        let mut output = String::with_capacity(17 * 31);
-        output.push_str("┌──────┬──────┬──────┬──────┐\n\r");
-
-        for row in 0..15 {
-            if row % 2 == 0 {
-                output.push_str("│      │      │      │      │");
-            } else if (row - 1) % 4 == 0 {
-                let board_row = (row - 1) / 4;
-                output.push('│');
-                for col in 0..4 {
-                    match self.board[board_row][col] {
-                        Some(val) => match coord {
-                            Some((r, c)) if r == board_row && c == col => output.push_str(
-                                &format!("{}{:>5}{} │", style::Bold, val, style::Reset)[..],
-                            ),
-                            _ => {
-                                let palette = match val {
-                                    8 => color::Rgb(0xff, 0xe4, 0xe6),
-                                    16 => color::Rgb(0xfe, 0xcd, 0xd3),
-                                    32 => color::Rgb(0xfd, 0xa4, 0xaf),
-                                    64 => color::Rgb(0xfb, 0x71, 0x85),
-                                    128 => color::Rgb(0xf4, 0x3f, 0x5e),
-                                    256 => color::Rgb(0xe1, 0x1d, 0x48),
-                                    512 => color::Rgb(0xbe, 0x12, 0x3c),
-                                    1024 => color::Rgb(0x9f, 0x12, 0x39),
-                                    2048 => color::Rgb(0x88, 0x13, 0x37),
-                                    _ => color::Rgb(0xff, 0xf1, 0xf2),
-                                };
-                                let color_str =
-                                    format!("{}{:>5}{} │", color::Fg(palette), val, style::Reset);
-                                output.push_str(&color_str[..]);
-                            }
-                        },
-                        None => output.push_str("      │"),
-                    };
-                }
-            } else {
-                output.push_str("├──────┼──────┼──────┼──────┤");
-            }
-            output.push_str("\n\r");
+    }
+
+    /// Replace the current state with `snapshot`, returning the state it replaced.
+    fn restore(&mut self, snapshot: Snapshot) -> Snapshot {
+        let current = self.snapshot();
+        self.board = snapshot.board;
+        self.score = snapshot.score;
+        self.status = snapshot.status;
+        self.moves = snapshot.moves;
+        self.rng = snapshot.rng;
+        current
+    }
+
+    /// Remember the state before a move, keeping no more history than undos remain.
+    fn push_undo(&mut self, snapshot: Snapshot) {
+        self.redo_stack.clear();
+        if self.undos_left() == Some(0) {
+            return;
         }
-        output.push_str("└──────┴──────┴──────┴──────┘\n\r");
-
-        output
-    }
-
-    fn check_is_end(&self) -> bool {
-        !self.board.iter().enumerate().any(|(r, row)| {
-            row.iter().enumerate().any(|(c, val)| {
-                let next_row = if r + 1 < 4 {
-                    self.board[r + 1][c]
-                } else {
-                    None
-                };
-                let next_col = if c + 1 < 4 {
-                    self.board[r][c + 1]
-                } else {
-                    None
-                };
-                val.is_none() || *val == next_row || *val == next_col
-            })
-        })
-    }
-
-    pub(crate) fn move_up(&mut self) -> (u32, bool) {
-        let mut max_tile: u32 = 0;
-        let mut changed = false;
-        for c in 0..4 {
-            let mut start: usize = 0;
-            let mut merged = false;
-            for r in 0..4 {
-                if let Some(val) = self.board[r][c].take() {
-                    if start == 0 || Some(val) != self.board[start - 1][c] || merged {
-                        max_tile = max_tile.max(val);
-                        if start != r {
-                            changed = true;
-                        }
-                        self.board[start][c] = Some(val);
-                        start += 1;
-                        merged = false;
-                    } else {
-                        let new_val = val + val;
-                        max_tile = max_tile.max(new_val);
-                        self.board[start - 1][c] = Some(new_val);
-                        self.score += new_val;
-                        merged = true;
-                        changed = true;
-                    }
-                }
-            }
+        self.undo_stack.push(snapshot);
+        if let Some(left) = self.undos_left() {
+            let excess = self.undo_stack.len().saturating_sub(left as usize);
+            self.undo_stack.drain(..excess);
         }
-        (max_tile, changed)
-    }
-
-    pub(crate) fn move_down(&mut self) -> (u32, bool) {
-        let mut max_tile: u32 = 0;
-        let mut changed = false;
-        for c in 0..4 {
-            let mut start: isize = 3;
-            let mut merged = false;
-            for r in (0..4).rev() {
-                if let Some(val) = self.board[r][c].take() {
-                    if start == 3 || Some(val) != self.board[start as usize + 1][c] || merged {
-                        max_tile = max_tile.max(val);
-                        if start as usize != r {
-                            changed = true;
-                        }
-                        self.board[start as usize][c] = Some(val);
-                        start -= 1;
-                        merged = false;
-                    } else {
-                        let new_val = val + val;
-                        max_tile = max_tile.max(new_val);
-                        self.board[start as usize + 1][c] = Some(new_val);
-                        self.score += new_val;
-                        merged = true;
-                        changed = true;
-                    }
-                }
-            }
+    }
+
+    /// The directions that would change the board if played now.
+    pub fn legal_moves(&self) -> Vec<Direction> {
+        Direction::ALL
+            .into_iter()
+            .filter(|&direction| self.is_legal(direction))
+            .collect()
+    }
+
+    pub fn is_legal(&self, direction: Direction) -> bool {
+        self.board.clone().shift(direction).1
+    }
+
+    /// Each value the spawner can place, with its probability.
+    pub fn spawn_odds(&self) -> Vec<(u32, f64)> {
+        self.spawn.odds()
+    }
+
+    fn set_rand(&mut self) -> Option<(usize, usize)> {
+        let available = self.board.empty_cells();
+        let len = available.len();
+        if len == 0 {
+            return None;
         }
-        (max_tile, changed)
-    }
-
-    pub(crate) fn move_left(&mut self) -> (u32, bool) {
-        let mut max_tile: u32 = 0;
-        let mut changed = false;
-        for row in self.board.iter_mut() {
-            let mut start: usize = 0;
-            let mut merged = false;
-            for i in 0..4 {
-                if let Some(val) = row[i].take() {
-                    if start == 0 || Some(val) != row[start - 1] || merged {
-                        max_tile = max_tile.max(val);
-                        if start != i {
-                            changed = true;
-                        }
-                        row[start] = Some(val);
-                        start += 1;
-                        merged = false;
-                    } else {
-                        let new_val = val + val;
-                        max_tile = max_tile.max(new_val);
-                        row[start - 1] = Some(new_val);
-                        self.score += new_val;
-                        merged = true;
-                        changed = true;
-                    }
-                }
-            }
+        if let Spawner::Evil { depth } = self.spawn.spawner {
+            let values: Vec<u32> = self.spawn.weights.iter().map(|&(val, _)| val).collect();
+            let ((r, c), val) = ai::worst_spawn(&self.board, &values, depth)?;
+            self.board.set(r, c, Some(val));
+            return Some((r, c));
         }
-        (max_tile, changed)
-    }
-
-    pub(crate) fn move_right(&mut self) -> (u32, bool) {
-        let mut max_tile: u32 = 0;
-        let mut changed = false;
-        for row in self.board.iter_mut() {
-            let mut start: isize = 3;
-            let mut merged = false;
-            for i in (0..4).rev() {
-                if let Some(val) = row[i].take() {
-                    if start == 3 || Some(val) != row[start as usize + 1] || merged {
-                        max_tile = max_tile.max(val);
-                        if start as usize != i {
-                            changed = true;
-                        }
-                        row[start as usize] = Some(val);
-                        start -= 1;
-                        merged = false;
-                    } else {
-                        let new_val = val + val;
-                        max_tile = max_tile.max(new_val);
-                        row[start as usize + 1] = Some(new_val);
-                        self.score += new_val;
-                        merged = true;
-                        changed = true;
-                    }
-                }
+        let (r, c) = available[self.rng.gen_range(0..len)];
+        let odds = self.spawn.odds();
+        let mut x = self.rng.gen::<f64>();
+        let mut new_val = odds[odds.len() - 1].0;
+        for (val, chance) in odds {
+            if x <= chance {
+                new_val = val;
+                break;
             }
+            x -= chance;
+        }
+        self.board.set(r, c, Some(new_val));
+        Some((r, c))
+    }
+
+    /// Slide the board towards `direction` and spawn a tile if anything moved.
+    ///
+    /// Returns the resulting status and the coordinate of the first tile spawned, if any.
+    pub fn move_board(&mut self, direction: Direction) -> (GameStatus, Option<(usize, usize)>) {
+        let (status, events) = self.move_events(direction);
+        let coord = events.iter().find_map(|event| match *event {
+            Event::Spawned { at, .. } => Some(at),
+            _ => None,
+        });
+        (status, coord)
+    }
+
+    /// Like [`Game::move_board`], but describes everything the move did.
+    ///
+    /// The events come in the order they happened: slides and merges line by line, then
+    /// the score gained, then the spawns. A move that changes nothing has no events.
+    pub fn move_events(&mut self, direction: Direction) -> (GameStatus, Vec<Event>) {
+        let before = self.snapshot();
+        let mut events = Vec::new();
+        let (gained, changed) = self.board.shift_events(direction, &mut events);
+        if !changed {
+            return (self.status, events);
+        }
+        self.push_undo(before);
+        self.score += gained;
+        self.moves += 1;
+        if gained > 0 {
+            events.push(Event::Scored(gained));
         }
-        (max_tile, changed)
+        for _ in 0..self.spawn.per_move {
+            let Some((r, c)) = self.set_rand() else {
+                break;
+            };
+            events.push(Event::Spawned {
+                at: (r, c),
+                value: self.board.get(r, c).unwrap(),
+            });
+        }
+        self.status = if self.board.max_tile() >= self.target && !self.keep_going {
+            GameStatus::Win
+        } else if self.board.is_stuck() {
+            GameStatus::Lost
+        } else {
+            GameStatus::Continue
+        };
+        (self.status, events)
     }
 }
 
@@ -317,6 +469,7 @@ impl Default for Game {
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Direction {
     Up,
     Down,
@@ -324,7 +477,50 @@ pub enum Direction {
     Right,
 }
 
-#[derive(PartialEq)]
+impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Down,
+        Direction::Left,
+        Direction::Right,
+    ];
+
+    /// The direction's name in lower case, as in `"up"`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Direction::Up => "up",
+            Direction::Down => "down",
+            Direction::Left => "left",
+            Direction::Right => "right",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Direction> {
+        Self::ALL
+            .into_iter()
+            .find(|direction| direction.name() == name)
+    }
+}
+
+/// Something that happened during a move, with cells given as `(row, col)`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event {
+    /// A tile of `value` slid from one cell to another. Both tiles of a merge that move
+    /// get one of these, ending on the merge cell.
+    Moved {
+        from: (usize, usize),
+        to: (usize, usize),
+        value: u32,
+    },
+    /// Two tiles combined into one of `value` at `at`.
+    Merged { at: (usize, usize), value: u32 },
+    /// Points gained from all merges in the move.
+    Scored(u32),
+    /// The spawner placed a tile.
+    Spawned { at: (usize, usize), value: u32 },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum GameStatus {
     Win,
     Lost,
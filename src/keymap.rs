@@ -0,0 +1,211 @@
+//! Which keys trigger which actions in the terminal front end.
+//!
+//! The defaults accept vim keys, WASD and the arrow keys for moves. The `[keys]` section
+//! of the config file rebinds an action to a space-separated list of keys, replacing its
+//! defaults:
+//!
+//! ```text
+//! [keys]
+//! up = k w Up
+//! save = Ctrl-s F2
+//! ```
+//!
+//! Keys are single characters, `Ctrl-x`, `Alt-x`, `F1` to `F12`, or one of `Up`, `Down`,
+//! `Left`, `Right`, `Home`, `End`, `PageUp`, `PageDown`, `Insert`, `Delete`,
+//! `Backspace`, `Esc`, `Enter`, `Tab` and `Space`.
+
+use crate::config::Config;
+use rust_2048::game::Direction;
+use std::collections::HashMap;
+use termion::event::Key;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Move(Direction),
+    Undo,
+    Redo,
+    /// Abandon the game and start a new one.
+    Restart,
+    Hint,
+    Autoplay,
+    Save,
+    /// Show the high-score table.
+    Scores,
+    Quit,
+}
+
+impl Action {
+    /// Every action with its name in the config file.
+    const NAMES: [(&'static str, Action); 12] = [
+        ("up", Action::Move(Direction::Up)),
+        ("down", Action::Move(Direction::Down)),
+        ("left", Action::Move(Direction::Left)),
+        ("right", Action::Move(Direction::Right)),
+        ("undo", Action::Undo),
+        ("redo", Action::Redo),
+        ("restart", Action::Restart),
+        ("hint", Action::Hint),
+        ("autoplay", Action::Autoplay),
+        ("save", Action::Save),
+        ("scores", Action::Scores),
+        ("quit", Action::Quit),
+    ];
+}
+
+#[derive(Clone, Debug)]
+pub struct Keymap {
+    bindings: HashMap<Key, Action>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        use Key::{Char, Ctrl};
+        let defaults = [
+            (
+                Action::Move(Direction::Up),
+                &[Char('k'), Char('w'), Key::Up][..],
+            ),
+            (
+                Action::Move(Direction::Down),
+                &[Char('j'), Char('s'), Key::Down],
+            ),
+            (
+                Action::Move(Direction::Left),
+                &[Char('h'), Char('a'), Key::Left],
+            ),
+            (
+                Action::Move(Direction::Right),
+                &[Char('l'), Char('d'), Key::Right],
+            ),
+            (Action::Undo, &[Char('u')]),
+            (Action::Redo, &[Ctrl('r')]),
+            (Action::Restart, &[Char('n')]),
+            (Action::Hint, &[Char('t')]),
+            (Action::Autoplay, &[Char('p')]),
+            (Action::Save, &[Ctrl('s')]),
+            (Action::Scores, &[Char('b')]),
+            (Action::Quit, &[Char('q'), Ctrl('c')]),
+        ];
+        let bindings = defaults
+            .into_iter()
+            .flat_map(|(action, keys)| keys.iter().map(move |&key| (key, action)))
+            .collect();
+        Self { bindings }
+    }
+}
+
+impl Keymap {
+    pub fn action(&self, key: Key) -> Option<Action> {
+        self.bindings.get(&key).copied()
+    }
+
+    /// The name of a key bound to `action`, the shortest if there are several, to show
+    /// the player. `None` if nothing is bound to it.
+    pub fn label(&self, action: Action) -> Option<String> {
+        self.bindings
+            .iter()
+            .filter(|(_, bound)| **bound == action)
+            .map(|(&key, _)| key_name(key))
+            .min_by(|a, b| a.len().cmp(&b.len()).then(a.cmp(b)))
+    }
+
+    /// Rebind the actions named in the `[keys]` section of `config`.
+    ///
+    /// A key may take over a default binding, but not one given to another action in the
+    /// same section.
+    pub fn apply(&mut self, config: &Config) -> Result<(), String> {
+        let mut configured = HashMap::new();
+        for setting in config.section("keys") {
+            let action = Action::NAMES
+                .iter()
+                .find(|(name, _)| *name == setting.name)
+                .map(|&(_, action)| action)
+                .ok_or_else(|| {
+                    config.error(setting, &format!("unknown action `{}`", setting.name))
+                })?;
+            let keys = setting
+                .value
+                .split_whitespace()
+                .map(|name| {
+                    parse_key(name)
+                        .ok_or_else(|| config.error(setting, &format!("unknown key `{name}`")))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            for (key, name) in keys.iter().zip(setting.value.split_whitespace()) {
+                match configured.insert(*key, setting.name.as_str()) {
+                    Some(other) if other != setting.name => {
+                        return Err(config.error(
+                            setting,
+                            &format!("key `{name}` is already bound to `{other}`"),
+                        ))
+                    }
+                    _ => {}
+                }
+            }
+            self.bindings.retain(|_, bound| *bound != action);
+            for key in keys {
+                self.bindings.insert(key, action);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The key called `name` in the config file.
+pub(crate) fn parse_key(name: &str) -> Option<Key> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(Key::Char(c));
+    }
+    let single = |rest: &str| {
+        let mut chars = rest.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Some(c),
+            _ => None,
+        }
+    };
+    if let Some(rest) = name.strip_prefix("Ctrl-") {
+        return single(rest).map(Key::Ctrl);
+    }
+    if let Some(rest) = name.strip_prefix("Alt-") {
+        return single(rest).map(Key::Alt);
+    }
+    if let Some(n) = name.strip_prefix('F').and_then(|n| n.parse().ok()) {
+        return (1..=12).contains(&n).then_some(Key::F(n));
+    }
+    let key = match name {
+        "Up" => Key::Up,
+        "Down" => Key::Down,
+        "Left" => Key::Left,
+        "Right" => Key::Right,
+        "Home" => Key::Home,
+        "End" => Key::End,
+        "PageUp" => Key::PageUp,
+        "PageDown" => Key::PageDown,
+        "Insert" => Key::Insert,
+        "Delete" => Key::Delete,
+        "Backspace" => Key::Backspace,
+        "Esc" => Key::Esc,
+        "Enter" => Key::Char('\n'),
+        "Tab" => Key::Char('\t'),
+        "Space" => Key::Char(' '),
+        _ => return None,
+    };
+    Some(key)
+}
+
+/// The name of `key` as [`parse_key`] reads it.
+pub fn key_name(key: Key) -> String {
+    match key {
+        Key::Char('\n') => "Enter".to_string(),
+        Key::Char('\t') => "Tab".to_string(),
+        Key::Char(' ') => "Space".to_string(),
+        Key::Char(c) => c.to_string(),
+        Key::Ctrl(c) => format!("Ctrl-{c}"),
+        Key::Alt(c) => format!("Alt-{c}"),
+        Key::F(n) => format!("F{n}"),
+        Key::BackTab => "BackTab".to_string(),
+        Key::Null => "Null".to_string(),
+        key => format!("{key:?}"),
+    }
+}
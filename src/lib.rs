@@ -0,0 +1,27 @@
+//! A headless 2048 engine.
+//!
+//! The [`game`] module holds the rules: spawning new tiles and deciding when the game
+//! is won or lost, on top of the sliding and merging implemented by [`board::Board`].
+//! It does no terminal I/O, so bots, tests and alternate front ends can drive it
+//! directly. Games can be written to disk and resumed with the [`save`] module,
+//! or recorded and played back with [`replay`]. The [`ai`] module chooses moves for bots
+//! and autoplay, and [`scores`] keeps the high-score table. Agents can be trained
+//! against the engine through the Gym-style interface in [`env`], and programs in any
+//! language can play over the line-based [`protocol`] or the JSON [`server`]. Two
+//! players can [`race`] each other over the network, and any game can be watched live
+//! through [`broadcast`].
+
+pub mod ai;
+pub mod bitboard;
+pub mod board;
+pub mod broadcast;
+pub mod env;
+pub mod game;
+pub mod protocol;
+pub mod race;
+pub mod replay;
+pub mod save;
+pub mod scores;
+pub mod server;
+
+mod tests;
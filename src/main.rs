@@ -1,11 +1,462 @@
-use std::io;
-use game::Game;
+use rust_2048::{
+    ai::PlayerKind,
+    board::{MAX_SIZE, MIN_SIZE},
+    broadcast::{Broadcaster, Spectator},
+    game::{Game, GameConfig, GameResult, GameStatus, SpawnPolicy, Spawner},
+    protocol,
+    race::{Race, Winner},
+    replay::Replay,
+    scores::Leaderboard,
+    server::Server,
+};
+use std::{
+    io::{self, prelude::*},
+    net::TcpListener,
+    os::unix::net::UnixListener,
+    path::PathBuf,
+    process,
+    str::FromStr,
+    time::Duration,
+};
 
-mod game;
+mod config;
+mod keymap;
+mod terminal;
+mod theme;
+mod tui;
+// The front end's tests live beside the engine's, in src/tests.
+#[path = "tests/front_end.rs"]
 mod tests;
 
+const USAGE: &str = "\
+Usage: rust_2048 [OPTIONS]
+
+Play 2048 in the terminal.
+
+Game:
+  -r, --rows N            Rows on the board, from 2 to 16 [default: 4]
+  -c, --cols N            Columns on the board, from 2 to 16 [default: 4]
+  -s, --seed N            Seed for the tile spawner [default: random]
+  -t, --target N          Tile that wins the game, a power of two [default: 2048]
+  -u, --undo-limit N      Moves that may be taken back [default: no limit]
+      --spawn ODDS        Weight of each spawned value [default: 2:0.9,4:0.1]
+      --spawn-count N     Tiles spawned after each move [default: 1]
+      --start-tiles N     Tiles on the board at the start [default: 2]
+      --evil              Let an adversary place each tile where it hurts most
+      --evil-depth N      Moves the adversary looks ahead, from 1 to 4 [default: 2];
+                          fewer while many cells are empty, to keep it quick
+
+Files:
+      --save FILE         Save here with the save key and when quitting mid-game
+      --resume FILE       Resume a saved game, saving back to the same file
+      --record FILE       Record a replay of the game
+      --replay FILE       Watch a recorded replay instead of playing
+      --speed N           Replay steps per second [default: 4]
+      --scores            List the high scores and exit
+      --scores-file FILE  Where high scores are kept
+      --config FILE       Settings file for keys and themes
+
+AI:
+      --autoplay          Start with the AI playing
+      --ai NAME           expectimax, greedy or random [default: expectimax]
+      --depth N           Expectimax search depth, from 1 to 8 [default: 3]
+      --delay MS          Pause between AI moves [default: 100]
+      --headless          Let the AI play without a terminal and print the result
+      --bot               Let another program play over stdin and stdout
+
+Network:
+      --serve ADDR        Host games as JSON over HOST:PORT or unix:PATH
+      --host ADDR         Wait at HOST:PORT for an opponent to race
+      --join ADDR         Race the opponent hosting at HOST:PORT
+      --broadcast ADDR    Let spectators watch the game from HOST:PORT
+      --watch ADDR        Watch the game broadcast at HOST:PORT
+
+Display:
+      --theme NAME        Colour theme [default: rose]
+      --color MODE        auto, truecolor, 256, 16 or none [default: auto]
+      --ascii             Draw lines in plain ASCII
+      --animation MS      Length of move animations [default: 100]
+      --no-animation      Turn animations off
+
+  -h, --help              Print this help and exit
+  -V, --version           Print the version and exit
+
+Bad arguments exit with status 2, and other errors with status 1.
+";
+
 fn main() -> io::Result<()> {
-    let mut game = Game::new();
-    game.start()?;
+    let mut args = std::env::args().skip(1);
+    let mut config = GameConfig::default();
+    let mut options = tui::Options::default();
+    let mut resume: Option<PathBuf> = None;
+    let mut replay: Option<PathBuf> = None;
+    let mut speed: f64 = 4.0;
+    let mut list_scores = false;
+    let mut scores_path = Leaderboard::default_path();
+    let mut config_path = config::Config::default_path();
+    let mut theme: Option<String> = None;
+    let mut color = None;
+    let mut ascii = false;
+    let mut headless = false;
+    let mut bot = false;
+    let mut serve: Option<String> = None;
+    let mut host: Option<String> = None;
+    let mut join: Option<String> = None;
+    let mut broadcast: Option<String> = None;
+    let mut watch: Option<String> = None;
+
+    while let Some(arg) = args.next() {
+        // Long options also take their value as `--name=value`.
+        let (flag, mut inline) = match arg.split_once('=') {
+            Some((flag, value)) if arg.starts_with("--") => {
+                (flag.to_string(), Some(value.to_string()))
+            }
+            _ => (arg, None),
+        };
+        let mut value = || inline.take().or_else(|| args.next());
+        match flag.as_str() {
+            "-r" | "--rows" => config.rows = parse_size(value(), "rows"),
+            "-c" | "--cols" => config.cols = parse_size(value(), "cols"),
+            "-u" | "--undo-limit" => config.undo_limit = Some(parse(value(), "undo limit")),
+            "-s" | "--seed" => config.seed = Some(parse(value(), "seed")),
+            "-t" | "--target" => {
+                config.target = parse(value(), "target");
+                if !config.target.is_power_of_two() || config.target < 4 {
+                    usage_error("target must be a power of two from 4 up.");
+                }
+            }
+            "--spawn" => {
+                let weights: String = parse(value(), "spawn odds");
+                config.spawn.weights =
+                    SpawnPolicy::parse_weights(&weights).unwrap_or_else(|err| usage_error(&err));
+            }
+            "--spawn-count" => config.spawn.per_move = parse(value(), "spawn count"),
+            "--start-tiles" => config.spawn.start = parse(value(), "start tiles"),
+            "--evil" => {
+                if config.spawn.spawner == Spawner::Random {
+                    config.spawn.spawner = Spawner::Evil { depth: 2 };
+                }
+            }
+            "--evil-depth" => {
+                let depth = parse(value(), "evil depth");
+                if !(1..=4).contains(&depth) {
+                    usage_error("evil depth must be from 1 to 4.");
+                }
+                config.spawn.spawner = Spawner::Evil { depth };
+            }
+            "--save" => options.save_path = Some(parse(value(), "save file")),
+            "--resume" => resume = Some(parse(value(), "save file")),
+            "--record" => options.record_path = Some(parse(value(), "replay file")),
+            "--replay" => replay = Some(parse(value(), "replay file")),
+            "--config" => config_path = Some(parse(value(), "config file")),
+            "--theme" => theme = Some(parse(value(), "theme")),
+            "--color" => {
+                let name: String = parse(value(), "colour mode");
+                color = terminal::ColorMode::parse(&name).unwrap_or_else(|| {
+                    usage_error("colour mode must be auto, truecolor, 256, 16 or none.")
+                });
+            }
+            "--ascii" => ascii = true,
+            "--scores" => list_scores = true,
+            "--scores-file" => scores_path = Some(parse(value(), "scores file")),
+            "--autoplay" => options.autoplay = true,
+            "--ai" => {
+                let name: String = parse(value(), "AI");
+                options.ai = PlayerKind::from_name(&name)
+                    .unwrap_or_else(|| usage_error("AI must be expectimax, greedy or random."));
+            }
+            "--depth" => {
+                options.ai_depth = parse(value(), "depth");
+                if !(1..=8).contains(&options.ai_depth) {
+                    usage_error("depth must be from 1 to 8.");
+                }
+            }
+            "--delay" => options.autoplay_delay = Duration::from_millis(parse(value(), "delay")),
+            "--headless" => headless = true,
+            "--bot" => bot = true,
+            "--serve" => serve = Some(parse(value(), "address")),
+            "--host" => host = Some(parse(value(), "address")),
+            "--join" => join = Some(parse(value(), "address")),
+            "--broadcast" => broadcast = Some(parse(value(), "address")),
+            "--watch" => watch = Some(parse(value(), "address")),
+            "--animation" => {
+                options.animation = Duration::from_millis(parse(value(), "animation length"))
+            }
+            "--no-animation" => options.animation = Duration::ZERO,
+            "--speed" => {
+                speed = parse(value(), "speed");
+                if !speed.is_finite() || speed <= 0.0 {
+                    usage_error("speed must be positive.");
+                }
+            }
+            "-h" | "--help" => {
+                print!("{USAGE}");
+                return Ok(());
+            }
+            "-V" | "--version" => {
+                println!("rust_2048 {}", env!("CARGO_PKG_VERSION"));
+                return Ok(());
+            }
+            _ => usage_error(&format!("unknown argument `{flag}`.")),
+        }
+        if inline.is_some() {
+            usage_error(&format!("{flag} takes no value."));
+        }
+    }
+
+    if let Err(err) = config.spawn.validate() {
+        usage_error(&format!("{err}."));
+    }
+    // The save file decides everything about the game itself.
+    if resume.is_some() && config != GameConfig::default() {
+        usage_error("--resume cannot be combined with options that set up the game.");
+    }
+    if headless && replay.is_some() {
+        usage_error("--headless cannot be combined with --replay.");
+    }
+    if bot && (headless || replay.is_some()) {
+        usage_error("--bot cannot be combined with --headless or --replay.");
+    }
+    if serve.is_some() && (bot || headless || replay.is_some() || resume.is_some()) {
+        usage_error("--serve cannot be combined with --bot, --headless, --replay or --resume.");
+    }
+    let racing = host.is_some() || join.is_some();
+    if racing && (serve.is_some() || bot || headless || replay.is_some() || resume.is_some()) {
+        usage_error(
+            "--host and --join cannot be combined with --serve, --bot, --headless, --replay or --resume.",
+        );
+    }
+    if host.is_some() && join.is_some() {
+        usage_error("--host cannot be combined with --join.");
+    }
+    if watch.is_some()
+        && (racing || serve.is_some() || bot || headless || replay.is_some() || broadcast.is_some())
+    {
+        usage_error("--watch only watches: it cannot be combined with other modes.");
+    }
+    if broadcast.is_some() && (serve.is_some() || bot || replay.is_some()) {
+        usage_error("--broadcast cannot be combined with --serve, --bot or --replay.");
+    }
+    // The host decides the game.
+    if join.is_some() && config != GameConfig::default() {
+        usage_error("--join cannot be combined with options that set up the game.");
+    }
+
+    if list_scores {
+        let path = scores_path.unwrap_or_else(|| fail("no scores file; set --scores-file."));
+        let board = Leaderboard::load_from_file(&path).unwrap_or_else(|err| {
+            fail(&format!("could not read scores {}: {err}", path.display()))
+        });
+        print!("{}", tui::leaderboard_to_string(&board, usize::MAX));
+        return Ok(());
+    }
+
+    options.broadcast = broadcast.map(|addr| {
+        let listener = TcpListener::bind(&addr)
+            .unwrap_or_else(|err| fail(&format!("could not listen on {addr}: {err}")));
+        if let Ok(addr) = listener.local_addr() {
+            eprintln!("Broadcasting on {addr}");
+        }
+        Broadcaster::new(listener)
+    });
+
+    if headless {
+        let mut game = new_game(config, resume.as_ref());
+        let result = play_headless(&mut game, &options)?;
+        print!("{}", game.board());
+        print_result(&result);
+        return Ok(());
+    }
+
+    if let Some(addr) = serve {
+        return serve_games(&addr, config);
+    }
+
+    if bot {
+        let mut game = new_game(config, resume.as_ref());
+        let mut replay = options.record_path.as_ref().map(|_| Replay::record(&game));
+        protocol::run(
+            &mut game,
+            io::stdin().lock(),
+            io::stdout().lock(),
+            replay.as_mut(),
+        )?;
+        if let (Some(path), Some(replay)) = (&options.record_path, &replay) {
+            replay.save_to_file(path)?;
+        }
+        return Ok(());
+    }
+
+    if !termion::is_tty(&io::stdin()) || !termion::is_tty(&io::stdout()) {
+        fail("not a terminal; use --bot to play over pipes or --headless to let the AI play.");
+    }
+
+    options.scores_path = scores_path;
+    let settings = match config_path {
+        Some(path) => config::Config::load(&path).unwrap_or_else(|err| fail(&err)),
+        None => config::Config::default(),
+    };
+    options
+        .keymap
+        .apply(&settings)
+        .unwrap_or_else(|err| fail(&err));
+    // The flag wins over the config file.
+    let theme = theme
+        .or_else(|| {
+            settings
+                .section("theme")
+                .find(|setting| setting.name == "name")
+                .map(|setting| setting.value.clone())
+        })
+        .unwrap_or_else(|| theme::DEFAULT.to_string());
+    let caps = terminal::Capabilities::detect();
+    options.theme = theme::Theme::load(&theme, &settings)
+        .unwrap_or_else(|err| fail(&err))
+        .for_terminal(color.unwrap_or(caps.color), ascii || !caps.unicode);
+
+    if let Some(addr) = watch {
+        let mut spectator = Spectator::connect(&addr)
+            .unwrap_or_else(|err| fail(&format!("could not watch {addr}: {err}")));
+        return tui::watch(&mut spectator, &options.theme);
+    }
+
+    if let Some(path) = replay {
+        let replay = Replay::load_from_file(&path).unwrap_or_else(|err| {
+            fail(&format!("could not load replay {}: {err}", path.display()))
+        });
+        return tui::play_replay(&replay, speed, &options.theme);
+    }
+
+    if racing {
+        let mut race = match (host, join) {
+            (Some(addr), _) => {
+                let listener = TcpListener::bind(&addr)
+                    .unwrap_or_else(|err| fail(&format!("could not listen on {addr}: {err}")));
+                eprintln!("Waiting for an opponent on {}...", listener.local_addr()?);
+                Race::host(&listener, &config)
+            }
+            (_, Some(addr)) => Race::join(&addr),
+            _ => unreachable!(),
+        }
+        .unwrap_or_else(|err| fail(&format!("could not start the race: {err}")));
+        let mut game = race.game();
+        let winner = tui::play_race(&mut game, &options, &mut race)?;
+        print_result(&game.result());
+        match winner {
+            Some(Winner::Draw) => println!("The race was a draw."),
+            Some(winner) if winner == race.side() => println!("You won the race."),
+            Some(_) => println!("You lost the race."),
+            None => println!("You left before the race was decided."),
+        }
+        return Ok(());
+    }
+
+    let mut game = new_game(config, resume.as_ref());
+    // Keep saving to the file we resumed from unless told otherwise.
+    if options.save_path.is_none() {
+        options.save_path = resume;
+    }
+    let result = tui::start(&mut game, &options)?;
+    println!();
+    print_result(&result);
     Ok(())
 }
+
+/// The game resumed from `resume`, or a new one.
+fn new_game(config: GameConfig, resume: Option<&PathBuf>) -> Game {
+    match resume {
+        Some(path) => Game::load_from_file(path).unwrap_or_else(|err| {
+            fail(&format!("could not resume from {}: {err}", path.display()))
+        }),
+        None => {
+            let mut game = Game::with_config(config);
+            game.reset();
+            game
+        }
+    }
+}
+
+/// Let the AI play until it wins or gets stuck, recording a replay if asked to.
+fn play_headless(game: &mut Game, options: &tui::Options) -> io::Result<GameResult> {
+    let mut replay = options.record_path.as_ref().map(|_| Replay::record(game));
+    let mut ai = options.ai.player(options.ai_depth, game.seed());
+    options.publish(game);
+    while game.status() == GameStatus::Continue {
+        let Some(direction) = ai.choose(game) else {
+            break;
+        };
+        let (_, events) = game.move_events(direction);
+        if let Some(replay) = &mut replay {
+            replay.push_move(direction, &events);
+        }
+        if !events.is_empty() {
+            options.publish(game);
+        }
+    }
+    if let (Some(path), Some(replay)) = (&options.record_path, &replay) {
+        replay.save_to_file(path)?;
+    }
+    Ok(game.result())
+}
+
+/// Host games for clients at `addr`, either `unix:PATH` or a TCP address.
+fn serve_games(addr: &str, config: GameConfig) -> io::Result<()> {
+    let server = Server::new(config);
+    let listen_error =
+        |err: io::Error| -> ! { fail(&format!("could not listen on {addr}: {err}")) };
+    let accept_error = |err: io::Error| eprintln!("could not accept a connection: {err}");
+    match addr.strip_prefix("unix:") {
+        Some(path) => {
+            let listener = UnixListener::bind(path).unwrap_or_else(|err| listen_error(err));
+            eprintln!("Listening on {addr}");
+            server.serve_unix(listener, accept_error)
+        }
+        None => {
+            let listener = TcpListener::bind(addr).unwrap_or_else(|err| listen_error(err));
+            eprintln!("Listening on {}", listener.local_addr()?);
+            server.serve_tcp(listener, accept_error)
+        }
+    }
+}
+
+fn print_result(result: &GameResult) {
+    println!(
+        "Final score: {} (max tile {}, {} moves{})",
+        result.score,
+        result.max_tile,
+        result.moves,
+        if result.undo_used { ", undo used" } else { "" }
+    );
+}
+
+/// Parse an option's value, exiting with an error message if it is missing or invalid.
+fn parse<T: FromStr>(arg: Option<String>, name: &str) -> T {
+    let arg = arg.unwrap_or_else(|| usage_error(&format!("no {name} given.")));
+    arg.parse()
+        .unwrap_or_else(|_| usage_error(&format!("invalid {name} given.")))
+}
+
+/// Parse a board dimension, exiting with an error message if it is missing or invalid.
+fn parse_size(arg: Option<String>, name: &str) -> usize {
+    let n = parse(arg, name);
+    if !(MIN_SIZE..=MAX_SIZE).contains(&n) {
+        usage_error(&format!("{name} must be from {MIN_SIZE} to {MAX_SIZE}."));
+    }
+    n
+}
+
+/// Exit for a bad command line, pointing at `--help`.
+fn usage_error(msg: &str) -> ! {
+    exit(&format!("{msg}\nRun `rust_2048 --help` for usage."), 2)
+}
+
+fn fail(msg: &str) -> ! {
+    exit(msg, 1)
+}
+
+fn exit(msg: &str, code: i32) -> ! {
+    let mut stderr = io::stderr().lock();
+    writeln!(stderr, "{msg}").unwrap();
+    stderr.flush().unwrap();
+    process::exit(code);
+}
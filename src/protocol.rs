@@ -0,0 +1,154 @@
+//! A line-based text protocol for playing a game from another program over stdin and
+//! stdout.
+//!
+//! The engine speaks first, giving the protocol version, the board size, the target
+//! tile and the starting position:
+//!
+//! ```text
+//! rust_2048 bot 1
+//! size 4 4
+//! target 2048
+//! state 0 0 playing
+//! 0 0 0 0
+//! 0 2 0 0
+//! 0 0 0 0
+//! 2 0 0 0
+//! ```
+//!
+//! A `state` block gives the score, the number of moves and the status (`playing`,
+//! `won` or `lost`), then one line per row with `0` for empty cells. The program then
+//! sends one command per line: `up`, `down`, `left`, `right`, `undo` or `quit`. Blank
+//! lines are ignored. The engine answers every command but `quit` with one result line
+//! followed by a `state` block:
+//!
+//! ```text
+//! moved left 4 1,0=2
+//! unmoved up
+//! undone
+//! error the game is over
+//! ```
+//!
+//! `moved` gives the direction, the points scored and each tile spawned as
+//! `row,col=value`. `unmoved` means the move changed nothing, and `error` that the
+//! command was refused. Once the game is won or lost only `undo` and `quit` are
+//! accepted. `quit`, or the end of the input, is answered with `bye` and ends the
+//! session.
+
+use crate::{
+    game::{Direction, Event, Game, GameStatus},
+    replay::Replay,
+};
+use std::io::{self, prelude::*};
+
+/// The protocol version written in the greeting.
+pub const VERSION: u32 = 1;
+
+/// A line sent by the program.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Command {
+    Move(Direction),
+    Undo,
+    Quit,
+}
+
+impl Command {
+    pub fn parse(line: &str) -> Result<Command, String> {
+        match line.trim() {
+            "undo" => Ok(Command::Undo),
+            "quit" => Ok(Command::Quit),
+            word => Direction::from_name(word)
+                .map(Command::Move)
+                .ok_or_else(|| format!("unknown command `{word}`")),
+        }
+    }
+}
+
+/// Play `game` with commands read from `input`, writing the engine's side to `output`
+/// and recording to `replay` if given, until the program quits or the input ends.
+pub fn run<R: BufRead, W: Write>(
+    game: &mut Game,
+    input: R,
+    mut output: W,
+    mut replay: Option<&mut Replay>,
+) -> io::Result<()> {
+    writeln!(output, "rust_2048 bot {VERSION}")?;
+    writeln!(
+        output,
+        "size {} {}",
+        game.board().rows(),
+        game.board().cols()
+    )?;
+    writeln!(output, "target {}", game.target())?;
+    write_state(&mut output, game)?;
+    output.flush()?;
+
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match Command::parse(&line) {
+            Ok(Command::Quit) => break,
+            Ok(Command::Move(_)) if game.status() != GameStatus::Continue => {
+                writeln!(output, "error the game is over")?;
+            }
+            Ok(Command::Move(direction)) => {
+                let (_, events) = game.move_events(direction);
+                if let Some(replay) = replay.as_deref_mut() {
+                    replay.push_move(direction, &events);
+                }
+                write_move(&mut output, direction, &events)?;
+            }
+            Ok(Command::Undo) => {
+                if game.undo() {
+                    if let Some(replay) = replay.as_deref_mut() {
+                        replay.push_undo();
+                    }
+                    writeln!(output, "undone")?;
+                } else {
+                    writeln!(output, "error nothing to undo")?;
+                }
+            }
+            Err(err) => writeln!(output, "error {err}")?,
+        }
+        write_state(&mut output, game)?;
+        output.flush()?;
+    }
+    writeln!(output, "bye")?;
+    output.flush()
+}
+
+/// The result line for a move that produced `events`.
+fn write_move<W: Write>(output: &mut W, direction: Direction, events: &[Event]) -> io::Result<()> {
+    if events.is_empty() {
+        return writeln!(output, "unmoved {}", direction.name());
+    }
+    let gained = events
+        .iter()
+        .map(|event| match *event {
+            Event::Scored(points) => points,
+            _ => 0,
+        })
+        .sum::<u32>();
+    write!(output, "moved {} {gained}", direction.name())?;
+    for event in events {
+        if let Event::Spawned { at: (r, c), value } = *event {
+            write!(output, " {r},{c}={value}")?;
+        }
+    }
+    writeln!(output)
+}
+
+fn write_state<W: Write>(output: &mut W, game: &Game) -> io::Result<()> {
+    let status = match game.status() {
+        GameStatus::Continue => "playing",
+        GameStatus::Win => "won",
+        GameStatus::Lost => "lost",
+    };
+    writeln!(output, "state {} {} {status}", game.score(), game.moves())?;
+    for row in game.board().iter_rows() {
+        let cells: Vec<String> = row.iter().map(|val| val.unwrap_or(0).to_string()).collect();
+        writeln!(output, "{}", cells.join(" "))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,275 @@
+//! Two players racing on the same spawn sequence over TCP.
+//!
+//! One side hosts and picks the game; the other joins and plays an identical copy of
+//! it. Both send their [`Progress`] after every move, so each can show the other's
+//! board. The host referees: it decides the race with [`decide`] as soon as it can
+//! and tells the guest with [`Message::Over`].
+//!
+//! Messages are JSON, one per line. The host speaks first with [`Message::Hello`].
+
+use crate::{
+    board::{MAX_SIZE, MIN_SIZE},
+    game::{Game, GameConfig, GameStatus, SpawnPolicy, Spawner},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    io::{self, prelude::*, BufReader},
+    net::{Shutdown, TcpListener, TcpStream, ToSocketAddrs},
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+};
+
+/// The race protocol version sent in [`Message::Hello`].
+pub const VERSION: u32 = 1;
+/// The deepest evil spawner a guest agrees to, since deeper ones take too long a move.
+const MAX_EVIL_DEPTH: u32 = 4;
+
+/// Where a player's game stands, as sent to the other side.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Progress {
+    /// Rows of cell values, 0 for empty.
+    pub board: Vec<Vec<u32>>,
+    pub score: u32,
+    pub moves: u32,
+    /// Reached the target tile.
+    pub won: bool,
+    /// No move is left.
+    pub stuck: bool,
+}
+
+impl Progress {
+    pub fn of(game: &Game) -> Self {
+        Self {
+            board: game
+                .board()
+                .iter_rows()
+                .map(|row| row.iter().map(|val| val.unwrap_or(0)).collect())
+                .collect(),
+            score: game.score(),
+            moves: game.moves(),
+            won: game.status() == GameStatus::Win,
+            stuck: game.status() == GameStatus::Lost,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Winner {
+    Host,
+    Guest,
+    Draw,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Message {
+    /// The game both sides play, sent by the host once the guest connects.
+    Hello {
+        version: u32,
+        seed: u64,
+        rows: usize,
+        cols: usize,
+        target: u32,
+        spawn: Vec<(u32, f64)>,
+        per_move: u32,
+        start: u32,
+        /// The evil spawner's depth, if the game uses one.
+        evil: Option<u32>,
+    },
+    Progress(Progress),
+    /// The host's decision, after which neither side plays on.
+    Over {
+        winner: Winner,
+    },
+    /// The other side left, or its connection was lost.
+    Quit,
+}
+
+/// Who won, if the race is decided: the first to the target tile, or the higher score
+/// once both are stuck. The host calls this after every change on either side, so at
+/// most one side can have just reached the target; should both have, the one that got
+/// there in fewer moves wins.
+pub fn decide(host: &Progress, guest: &Progress) -> Option<Winner> {
+    let by = |host: u32, guest: u32| match host.cmp(&guest) {
+        std::cmp::Ordering::Greater => Winner::Host,
+        std::cmp::Ordering::Less => Winner::Guest,
+        std::cmp::Ordering::Equal => Winner::Draw,
+    };
+    match (host.won, guest.won) {
+        (true, true) => Some(by(guest.moves, host.moves)),
+        (true, false) => Some(Winner::Host),
+        (false, true) => Some(Winner::Guest),
+        (false, false) if host.stuck && guest.stuck => Some(by(host.score, guest.score)),
+        (false, false) => None,
+    }
+}
+
+/// A connection to the other player.
+pub struct Race {
+    /// Whether this side hosts and so referees.
+    pub host: bool,
+    /// The game both sides play, with undo turned off.
+    pub config: GameConfig,
+    stream: TcpStream,
+    incoming: Receiver<Message>,
+}
+
+impl Race {
+    /// Wait for a guest on `listener` and start a race of the game set up by `config`,
+    /// picking a seed if it has none.
+    pub fn host(listener: &TcpListener, config: &GameConfig) -> io::Result<Race> {
+        let (stream, _) = listener.accept()?;
+        let config = GameConfig {
+            seed: Some(config.seed.unwrap_or_else(rand::random)),
+            undo_limit: Some(0),
+            ..config.clone()
+        };
+        let reader = BufReader::new(stream.try_clone()?);
+        let mut race = Race::new(true, config, reader, stream);
+        let spawn = &race.config.spawn;
+        let hello = Message::Hello {
+            version: VERSION,
+            seed: race.config.seed.unwrap(),
+            rows: race.config.rows,
+            cols: race.config.cols,
+            target: race.config.target,
+            spawn: spawn.weights.clone(),
+            per_move: spawn.per_move,
+            start: spawn.start,
+            evil: match spawn.spawner {
+                Spawner::Random => None,
+                Spawner::Evil { depth } => Some(depth),
+            },
+        };
+        race.send(&hello)?;
+        Ok(race)
+    }
+
+    /// Join the race hosted at `addr`, waiting for the host to say what game it is.
+    pub fn join<A: ToSocketAddrs>(addr: A) -> io::Result<Race> {
+        let stream = TcpStream::connect(addr)?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        let invalid = |reason: String| io::Error::new(io::ErrorKind::InvalidData, reason);
+        let hello = serde_json::from_str(&line)
+            .map_err(|err| invalid(format!("bad greeting from the host: {err}")))?;
+        let Message::Hello {
+            version,
+            seed,
+            rows,
+            cols,
+            target,
+            spawn,
+            per_move,
+            start,
+            evil,
+        } = hello
+        else {
+            return Err(invalid("the host did not start the race".to_string()));
+        };
+        if version != VERSION {
+            return Err(invalid(format!(
+                "the host speaks race protocol {version}, not {VERSION}"
+            )));
+        }
+        let config = GameConfig {
+            rows,
+            cols,
+            seed: Some(seed),
+            undo_limit: Some(0),
+            target,
+            spawn: SpawnPolicy {
+                weights: spawn,
+                per_move,
+                start,
+                spawner: evil.map_or(Spawner::Random, |depth| Spawner::Evil { depth }),
+            },
+        };
+        if ![rows, cols]
+            .iter()
+            .all(|size| (MIN_SIZE..=MAX_SIZE).contains(size))
+            || !target.is_power_of_two()
+            || target < 4
+            || evil.is_some_and(|depth| depth > MAX_EVIL_DEPTH)
+        {
+            return Err(invalid(
+                "the host's game is not one this build can play".to_string(),
+            ));
+        }
+        config.spawn.validate().map_err(invalid)?;
+        Ok(Race::new(false, config, reader, stream))
+    }
+
+    /// Start reading messages from `reader`, the other half of `stream`.
+    fn new(
+        host: bool,
+        config: GameConfig,
+        reader: BufReader<TcpStream>,
+        stream: TcpStream,
+    ) -> Race {
+        let (sender, incoming) = mpsc::channel();
+        // Read on a thread of its own so the game never waits on the network. Anything
+        // unreadable ends the race as if the other side had left.
+        thread::spawn(move || {
+            for line in reader.lines() {
+                let message = line
+                    .ok()
+                    .and_then(|line| serde_json::from_str(&line).ok())
+                    .unwrap_or(Message::Quit);
+                let quit = message == Message::Quit;
+                if sender.send(message).is_err() || quit {
+                    return;
+                }
+            }
+            let _ = sender.send(Message::Quit);
+        });
+        Race {
+            host,
+            config,
+            stream,
+            incoming,
+        }
+    }
+
+    /// This side, as [`decide`] would name it.
+    pub fn side(&self) -> Winner {
+        if self.host {
+            Winner::Host
+        } else {
+            Winner::Guest
+        }
+    }
+
+    /// A new game of the race, ready to play.
+    pub fn game(&self) -> Game {
+        let mut game = Game::with_config(self.config.clone());
+        game.reset();
+        game
+    }
+
+    pub fn send(&mut self, message: &Message) -> io::Result<()> {
+        let line = serde_json::to_string(message).map_err(io::Error::other)?;
+        writeln!(self.stream, "{line}")?;
+        self.stream.flush()
+    }
+
+    /// The next message from the other side, if one has arrived. Once the other side
+    /// has gone this is [`Message::Quit`] every time.
+    pub fn try_recv(&self) -> Option<Message> {
+        match self.incoming.try_recv() {
+            Ok(message) => Some(message),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Message::Quit),
+        }
+    }
+}
+
+impl Drop for Race {
+    /// Hang up, which also stops the reading thread, since it holds a clone of the
+    /// stream that would keep the connection open.
+    fn drop(&mut self) {
+        let _ = self.stream.shutdown(Shutdown::Both);
+    }
+}
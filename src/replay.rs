@@ -0,0 +1,318 @@
+//! Recording a game move by move and playing it back.
+//!
+//! A replay stores the starting position and every step taken, including the tile each
+//! move spawned, so playback needs no RNG and can step backwards as easily as forwards.
+//! The file format is one line per step:
+//!
+//! ```text
+//! rust_2048 replay 2
+//! size 4 4
+//! seed 1234
+//! score 0
+//! start 0,1=2 3,3=4
+//! L 2,3=2
+//! U 0,0=4
+//! u
+//! r
+//! ```
+//!
+//! `U`, `D`, `L` and `R` are moves followed by each tile they spawned as
+//! `row,col=value`; `u` and `r` are undo and redo. Version 1 files, in which a move
+//! spawns at most one tile, read the same way.
+
+use crate::{
+    board::Board,
+    game::{Direction, Event, Game},
+    save::{self, Reader, SaveError},
+};
+use std::{
+    fs::File,
+    io::{self, prelude::*, BufReader},
+    path::Path,
+};
+
+/// The first word of every replay file.
+const MAGIC: &str = "rust_2048 replay";
+/// The replay format version written by this build.
+pub const VERSION: u32 = 2;
+
+/// A tile placed on the board by the spawner.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Spawn {
+    pub row: usize,
+    pub col: usize,
+    pub value: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Step {
+    Move {
+        direction: Direction,
+        spawns: Vec<Spawn>,
+    },
+    Undo,
+    Redo,
+}
+
+/// The position after a step of a replay.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Frame {
+    pub board: Board,
+    pub score: u32,
+    /// The tiles spawned by the step that led here.
+    pub spawns: Vec<Spawn>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Replay {
+    pub seed: u64,
+    /// The position the recording started from.
+    pub start: Frame,
+    pub steps: Vec<Step>,
+}
+
+impl Replay {
+    /// Start recording from the game's current position.
+    pub fn record(game: &Game) -> Self {
+        Self {
+            seed: game.seed(),
+            start: Frame {
+                board: game.board().clone(),
+                score: game.score(),
+                spawns: Vec::new(),
+            },
+            steps: Vec::new(),
+        }
+    }
+
+    /// Record a call to [`Game::move_events`] that returned `events`.
+    ///
+    /// Moves that did not change the board are not recorded.
+    pub fn push_move(&mut self, direction: Direction, events: &[Event]) {
+        if events.is_empty() {
+            return;
+        }
+        let spawns = events
+            .iter()
+            .filter_map(|event| match *event {
+                Event::Spawned {
+                    at: (row, col),
+                    value,
+                } => Some(Spawn { row, col, value }),
+                _ => None,
+            })
+            .collect();
+        self.steps.push(Step::Move { direction, spawns });
+    }
+
+    pub fn push_undo(&mut self) {
+        self.steps.push(Step::Undo);
+    }
+
+    pub fn push_redo(&mut self) {
+        self.steps.push(Step::Redo);
+    }
+
+    /// The start position followed by the position after every step.
+    pub fn frames(&self) -> Vec<Frame> {
+        let mut player = Player::new(self.start.clone());
+        let mut frames = vec![self.start.clone()];
+        for step in &self.steps {
+            // Steps that cannot apply, which only happens in hand-built replays, repeat
+            // the previous frame.
+            player.apply(step);
+            frames.push(player.current.clone());
+        }
+        frames
+    }
+
+    pub fn save<W: Write>(&self, mut out: W) -> io::Result<()> {
+        writeln!(out, "{MAGIC} {VERSION}")?;
+        writeln!(
+            out,
+            "size {} {}",
+            self.start.board.rows(),
+            self.start.board.cols()
+        )?;
+        writeln!(out, "seed {}", self.seed)?;
+        writeln!(out, "score {}", self.start.score)?;
+        write!(out, "start")?;
+        for (r, row) in self.start.board.iter_rows().enumerate() {
+            for (c, val) in row.iter().enumerate() {
+                if let Some(val) = val {
+                    write!(out, " {r},{c}={val}")?;
+                }
+            }
+        }
+        writeln!(out)?;
+        for step in &self.steps {
+            match step {
+                Step::Move { direction, spawns } => {
+                    let letter = match direction {
+                        Direction::Up => 'U',
+                        Direction::Down => 'D',
+                        Direction::Left => 'L',
+                        Direction::Right => 'R',
+                    };
+                    write!(out, "{letter}")?;
+                    for Spawn { row, col, value } in spawns {
+                        write!(out, " {row},{col}={value}")?;
+                    }
+                    writeln!(out)?;
+                }
+                Step::Undo => writeln!(out, "u")?,
+                Step::Redo => writeln!(out, "r")?,
+            }
+        }
+        out.flush()
+    }
+
+    /// Read a replay written by [`Replay::save`], checking every step against the board.
+    pub fn load<R: BufRead>(input: R) -> Result<Replay, SaveError> {
+        let mut reader = Reader::new(input);
+        reader.header(MAGIC, 1..=VERSION)?;
+        let (rows, cols) = reader.size()?;
+        let [seed] = reader.field("seed")?;
+        let [score] = reader.field("score")?;
+
+        let line = reader.next_line()?;
+        let mut words = line.split_whitespace();
+        if words.next() != Some("start") {
+            return Err(reader.corrupt("expected `start`"));
+        }
+        let mut board = Board::new(rows, cols);
+        for word in words {
+            let spawn = parse_spawn(&reader, word, &board)?;
+            board.set(spawn.row, spawn.col, Some(spawn.value));
+        }
+        let start = Frame {
+            board,
+            score,
+            spawns: Vec::new(),
+        };
+
+        let mut player = Player::new(start.clone());
+        let mut steps = Vec::new();
+        while let Some(line) = reader.try_next_line()? {
+            let mut words = line.split_whitespace();
+            let step = match words.next() {
+                None => continue,
+                Some("u") => Step::Undo,
+                Some("r") => Step::Redo,
+                Some(letter) => {
+                    let direction = match letter {
+                        "U" => Direction::Up,
+                        "D" => Direction::Down,
+                        "L" => Direction::Left,
+                        "R" => Direction::Right,
+                        _ => return Err(reader.corrupt(&format!("unknown step `{letter}`"))),
+                    };
+                    let mut board = player.current.board.clone();
+                    board.shift(direction);
+                    let mut spawns = Vec::new();
+                    for word in words.by_ref() {
+                        let spawn = parse_spawn(&reader, word, &board)?;
+                        board.set(spawn.row, spawn.col, Some(spawn.value));
+                        spawns.push(spawn);
+                    }
+                    Step::Move { direction, spawns }
+                }
+            };
+            if words.next().is_some() {
+                return Err(reader.corrupt("unexpected text after step"));
+            }
+            if !player.apply(&step) {
+                return Err(reader.corrupt("step cannot be played from this position"));
+            }
+            steps.push(step);
+        }
+
+        Ok(Replay { seed, start, steps })
+    }
+
+    /// Save to `path`, replacing it only once the whole replay is written, as
+    /// [`Game::save_to_file`] does.
+    pub fn save_to_file<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        save::replace_file(path.as_ref(), |out| self.save(out))
+    }
+
+    pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<Replay, SaveError> {
+        Replay::load(BufReader::new(File::open(path)?))
+    }
+}
+
+/// Parse a `row,col=value` tile that must land on an empty cell of `board`.
+fn parse_spawn<R: BufRead>(
+    reader: &Reader<R>,
+    word: &str,
+    board: &Board,
+) -> Result<Spawn, SaveError> {
+    let invalid = || reader.corrupt(&format!("invalid tile `{word}`"));
+    let (coord, value) = word.split_once('=').ok_or_else(invalid)?;
+    let (row, col) = coord.split_once(',').ok_or_else(invalid)?;
+    let spawn = Spawn {
+        row: row.parse().map_err(|_| invalid())?,
+        col: col.parse().map_err(|_| invalid())?,
+        value: value.parse().map_err(|_| invalid())?,
+    };
+    if spawn.row >= board.rows()
+        || spawn.col >= board.cols()
+        || !spawn.value.is_power_of_two()
+        || board.get(spawn.row, spawn.col).is_some()
+    {
+        return Err(invalid());
+    }
+    Ok(spawn)
+}
+
+/// Steps through a replay, keeping the history needed to play undo and redo.
+struct Player {
+    current: Frame,
+    undo: Vec<Frame>,
+    redo: Vec<Frame>,
+}
+
+impl Player {
+    fn new(start: Frame) -> Self {
+        Self {
+            current: start,
+            undo: Vec::new(),
+            redo: Vec::new(),
+        }
+    }
+
+    /// Play `step`, returning `false` and leaving the position alone if it cannot apply.
+    fn apply(&mut self, step: &Step) -> bool {
+        match step {
+            Step::Move { direction, spawns } => {
+                let mut board = self.current.board.clone();
+                let (gained, changed) = board.shift(*direction);
+                if !changed {
+                    return false;
+                }
+                for spawn in spawns {
+                    if board.get(spawn.row, spawn.col).is_some() {
+                        return false;
+                    }
+                    board.set(spawn.row, spawn.col, Some(spawn.value));
+                }
+                let next = Frame {
+                    board,
+                    score: self.current.score + gained,
+                    spawns: spawns.clone(),
+                };
+                self.undo.push(std::mem::replace(&mut self.current, next));
+                self.redo.clear();
+            }
+            Step::Undo => match self.undo.pop() {
+                Some(frame) => self.redo.push(std::mem::replace(&mut self.current, frame)),
+                None => return false,
+            },
+            Step::Redo => match self.redo.pop() {
+                Some(frame) => self.undo.push(std::mem::replace(&mut self.current, frame)),
+                None => return false,
+            },
+        }
+        true
+    }
+}
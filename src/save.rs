@@ -0,0 +1,414 @@
+//! Saving a game to a file and resuming it later.
+//!
+//! Save files are plain text so they can be inspected by hand:
+//!
+//! ```text
+//! rust_2048 save 4
+//! size 4 4
+//! seed 1234
+//! undo_limit none
+//! undos 0
+//! target 2048
+//! keep_going false
+//! spawn 2:0.9 4:0.1
+//! spawn_per_move 1
+//! start_tiles 2
+//! spawner random
+//! state 36 7 continue 40
+//! . 2 . .
+//! 4 8 . .
+//! 16 . . .
+//! 2 . . .
+//! undo 1
+//! state 28 6 continue 32
+//! ...
+//! redo 0
+//! ```
+//!
+//! `spawner` is `random` or `evil` followed by the adversary's search depth. A `state`
+//! line holds the score, move count, status and the spawner's position in its
+//! random stream, followed by one line per board row where `.` is an empty cell. The
+//! current state comes first, then the undo and redo stacks from oldest to newest.
+//!
+//! Older files still load. Version 1 predates `target` and `keep_going`, so those games
+//! aim for 2048, versions 1 and 2 predate the `spawn` lines, so those games carry on
+//! spawning a 4 three times in ten as they always did, and versions 1 to 3 always spawn
+//! at random.
+
+use crate::{
+    board::{Board, MAX_SIZE, MIN_SIZE},
+    game::{Game, GameStatus, Snapshot, SpawnPolicy, Spawner},
+};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use std::{
+    error::Error,
+    fmt,
+    fs::{self, File},
+    io::{self, prelude::*, BufReader, BufWriter},
+    ops::RangeInclusive,
+    path::Path,
+    str::FromStr,
+};
+
+/// The first word of every save file.
+const MAGIC: &str = "rust_2048 save";
+/// The save format version written by this build.
+pub const VERSION: u32 = 4;
+
+/// Why a save or replay file could not be loaded.
+#[derive(Debug)]
+pub enum SaveError {
+    Io(io::Error),
+    /// The file was written by a different, incompatible version of the format.
+    Version {
+        found: u32,
+        expected: u32,
+    },
+    /// The file is not of the expected kind or is damaged; `line` is 1-based.
+    Corrupt {
+        line: usize,
+        reason: String,
+    },
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Io(err) => write!(f, "{err}"),
+            SaveError::Version { found, expected } => write!(
+                f,
+                "file format version {found} is not supported (expected {expected})"
+            ),
+            SaveError::Corrupt { line, reason } => {
+                write!(f, "corrupt file at line {line}: {reason}")
+            }
+        }
+    }
+}
+
+impl Error for SaveError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SaveError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for SaveError {
+    fn from(err: io::Error) -> Self {
+        SaveError::Io(err)
+    }
+}
+
+impl Game {
+    /// Write the whole game, including its undo history, in the save format.
+    pub fn save<W: Write>(&self, mut out: W) -> io::Result<()> {
+        writeln!(out, "{MAGIC} {VERSION}")?;
+        writeln!(out, "size {} {}", self.board.rows(), self.board.cols())?;
+        writeln!(out, "seed {}", self.seed)?;
+        match self.undo_limit {
+            Some(limit) => writeln!(out, "undo_limit {limit}")?,
+            None => writeln!(out, "undo_limit none")?,
+        }
+        writeln!(out, "undos {}", self.undos)?;
+        writeln!(out, "target {}", self.target)?;
+        writeln!(out, "keep_going {}", self.keep_going)?;
+        write!(out, "spawn")?;
+        for (value, weight) in &self.spawn.weights {
+            write!(out, " {value}:{weight}")?;
+        }
+        writeln!(out)?;
+        writeln!(out, "spawn_per_move {}", self.spawn.per_move)?;
+        writeln!(out, "start_tiles {}", self.spawn.start)?;
+        match self.spawn.spawner {
+            Spawner::Random => writeln!(out, "spawner random")?,
+            Spawner::Evil { depth } => writeln!(out, "spawner evil {depth}")?,
+        }
+        write_snapshot(&mut out, &self.snapshot())?;
+        for (name, stack) in [("undo", &self.undo_stack), ("redo", &self.redo_stack)] {
+            writeln!(out, "{name} {}", stack.len())?;
+            for snapshot in stack {
+                write_snapshot(&mut out, snapshot)?;
+            }
+        }
+        out.flush()
+    }
+
+    /// Read a game written by [`Game::save`].
+    pub fn load<R: BufRead>(input: R) -> Result<Game, SaveError> {
+        let mut reader = Reader::new(input);
+        let version = reader.header(MAGIC, 1..=VERSION)?;
+        let (rows, cols) = reader.size()?;
+        let [seed] = reader.field("seed")?;
+        let [undo_limit] = reader.field::<String, 1>("undo_limit")?;
+        let undo_limit = match undo_limit.as_str() {
+            "none" => None,
+            limit => Some(reader.parse(limit)?),
+        };
+        let [undos] = reader.field("undos")?;
+        let (target, keep_going) = if version >= 2 {
+            let [target] = reader.field::<u32, 1>("target")?;
+            if !target.is_power_of_two() || target < 4 {
+                return Err(reader.corrupt(&format!("invalid target `{target}`")));
+            }
+            let [keep_going] = reader.field("keep_going")?;
+            (target, keep_going)
+        } else {
+            (2048, false)
+        };
+        let spawn = if version >= 3 {
+            let line = reader.next_line()?;
+            let weights = line
+                .strip_prefix("spawn ")
+                .ok_or_else(|| reader.corrupt("expected `spawn`"))?;
+            let weights =
+                SpawnPolicy::parse_weights(weights).map_err(|err| reader.corrupt(&err))?;
+            let mut spawn = SpawnPolicy {
+                weights,
+                ..SpawnPolicy::default()
+            };
+            // Check the weights now, so that errors point at their line.
+            spawn.validate().map_err(|err| reader.corrupt(&err))?;
+            [spawn.per_move] = reader.field("spawn_per_move")?;
+            [spawn.start] = reader.field("start_tiles")?;
+            if version >= 4 {
+                let line = reader.next_line()?;
+                spawn.spawner = match line.split_whitespace().collect::<Vec<_>>()[..] {
+                    ["spawner", "random"] => Spawner::Random,
+                    ["spawner", "evil", depth] => Spawner::Evil {
+                        depth: reader.parse(depth)?,
+                    },
+                    _ => return Err(reader.corrupt("expected `spawner random` or `spawner evil`")),
+                };
+            }
+            spawn.validate().map_err(|err| reader.corrupt(&err))?;
+            spawn
+        } else {
+            SpawnPolicy::legacy()
+        };
+
+        let current = reader.snapshot(seed, rows, cols)?;
+        let mut stacks = [Vec::new(), Vec::new()];
+        for (name, stack) in ["undo", "redo"].into_iter().zip(stacks.iter_mut()) {
+            let [len] = reader.field::<usize, 1>(name)?;
+            for _ in 0..len {
+                stack.push(reader.snapshot(seed, rows, cols)?);
+            }
+        }
+        let [undo_stack, redo_stack] = stacks;
+
+        Ok(Game {
+            board: current.board,
+            score: current.score,
+            status: current.status,
+            moves: current.moves,
+            seed,
+            rng: current.rng,
+            undo_limit,
+            target,
+            spawn,
+            keep_going,
+            undos,
+            undo_stack,
+            redo_stack,
+        })
+    }
+
+    /// Save to `path`, replacing it only once the whole game is written, so that a
+    /// failure part way leaves any earlier save as it was.
+    pub fn save_to_file<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        replace_file(path.as_ref(), |out| self.save(out))
+    }
+
+    pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<Game, SaveError> {
+        Game::load(BufReader::new(File::open(path)?))
+    }
+}
+
+/// Write `path` with `write`, going through a temporary file beside it that replaces it
+/// only once everything is written and synced, so that a failure leaves the old file.
+pub(crate) fn replace_file(
+    path: &Path,
+    write: impl FnOnce(&mut BufWriter<File>) -> io::Result<()>,
+) -> io::Result<()> {
+    let mut name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "not a file name"))?
+        .to_owned();
+    name.push(".tmp");
+    let temp = path.with_file_name(name);
+    let written = File::create(&temp).and_then(|file| {
+        let mut out = BufWriter::new(file);
+        write(&mut out)?;
+        out.into_inner()
+            .map_err(|err| err.into_error())?
+            .sync_all()?;
+        fs::rename(&temp, path)
+    });
+    if written.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    written
+}
+
+fn write_snapshot<W: Write>(out: &mut W, snapshot: &Snapshot) -> io::Result<()> {
+    let status = match snapshot.status {
+        GameStatus::Win => "win",
+        GameStatus::Lost => "lost",
+        GameStatus::Continue => "continue",
+    };
+    writeln!(
+        out,
+        "state {} {} {} {}",
+        snapshot.score,
+        snapshot.moves,
+        status,
+        snapshot.rng.get_word_pos()
+    )?;
+    for row in snapshot.board.iter_rows() {
+        let cells: Vec<String> = row
+            .iter()
+            .map(|val| match val {
+                Some(val) => val.to_string(),
+                None => ".".to_string(),
+            })
+            .collect();
+        writeln!(out, "{}", cells.join(" "))?;
+    }
+    Ok(())
+}
+
+/// Line-by-line parser that remembers where it is for error messages.
+pub(crate) struct Reader<R> {
+    lines: io::Lines<R>,
+    line: usize,
+}
+
+impl<R: BufRead> Reader<R> {
+    pub(crate) fn new(input: R) -> Self {
+        Self {
+            lines: input.lines(),
+            line: 0,
+        }
+    }
+
+    /// Check the first line reads `magic version` with a supported version, and return it.
+    pub(crate) fn header(
+        &mut self,
+        magic: &str,
+        supported: RangeInclusive<u32>,
+    ) -> Result<u32, SaveError> {
+        let header = self.next_line()?;
+        let found = header
+            .strip_prefix(magic)
+            .and_then(|rest| rest.trim().parse().ok())
+            .ok_or_else(|| self.corrupt(&format!("not a {magic} file")))?;
+        if !supported.contains(&found) {
+            return Err(SaveError::Version {
+                found,
+                expected: *supported.end(),
+            });
+        }
+        Ok(found)
+    }
+
+    /// Read a `size rows cols` line.
+    pub(crate) fn size(&mut self) -> Result<(usize, usize), SaveError> {
+        let [rows, cols] = self.field("size")?;
+        if !(MIN_SIZE..=MAX_SIZE).contains(&rows) || !(MIN_SIZE..=MAX_SIZE).contains(&cols) {
+            return Err(self.corrupt(&format!("unsupported board size {rows}x{cols}")));
+        }
+        Ok((rows, cols))
+    }
+
+    pub(crate) fn corrupt(&self, reason: &str) -> SaveError {
+        SaveError::Corrupt {
+            line: self.line,
+            reason: reason.to_string(),
+        }
+    }
+
+    /// The next line, or `None` at the end of the file.
+    pub(crate) fn try_next_line(&mut self) -> Result<Option<String>, SaveError> {
+        self.line += 1;
+        Ok(self.lines.next().transpose()?)
+    }
+
+    pub(crate) fn next_line(&mut self) -> Result<String, SaveError> {
+        self.try_next_line()?
+            .ok_or_else(|| self.corrupt("unexpected end of file"))
+    }
+
+    pub(crate) fn parse<T: FromStr>(&self, word: &str) -> Result<T, SaveError> {
+        word.parse()
+            .map_err(|_| self.corrupt(&format!("invalid value `{word}`")))
+    }
+
+    /// Read a line of the form `name value...` with exactly `N` values.
+    pub(crate) fn field<T: FromStr, const N: usize>(
+        &mut self,
+        name: &str,
+    ) -> Result<[T; N], SaveError> {
+        let line = self.next_line()?;
+        self.split(&line, name)
+    }
+
+    /// Parse an already read line of the form `name value...` with exactly `N` values.
+    pub(crate) fn split<T: FromStr, const N: usize>(
+        &self,
+        line: &str,
+        name: &str,
+    ) -> Result<[T; N], SaveError> {
+        let mut words = line.split_whitespace();
+        if words.next() != Some(name) {
+            return Err(self.corrupt(&format!("expected `{name}`")));
+        }
+        let values = words
+            .map(|word| self.parse(word))
+            .collect::<Result<Vec<T>, _>>()?;
+        values
+            .try_into()
+            .map_err(|_| self.corrupt(&format!("expected {N} value(s) after `{name}`")))
+    }
+
+    fn snapshot(&mut self, seed: u64, rows: usize, cols: usize) -> Result<Snapshot, SaveError> {
+        let [score, moves, status, word_pos] = self.field::<String, 4>("state")?;
+        let status = match status.as_str() {
+            "win" => GameStatus::Win,
+            "lost" => GameStatus::Lost,
+            "continue" => GameStatus::Continue,
+            _ => return Err(self.corrupt(&format!("unknown status `{status}`"))),
+        };
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        rng.set_word_pos(self.parse(&word_pos)?);
+
+        let mut board = Board::new(rows, cols);
+        for r in 0..rows {
+            let line = self.next_line()?;
+            let cells: Vec<&str> = line.split_whitespace().collect();
+            if cells.len() != cols {
+                return Err(self.corrupt(&format!("expected {cols} cells in board row")));
+            }
+            for (c, cell) in cells.into_iter().enumerate() {
+                if cell == "." {
+                    continue;
+                }
+                let val: u32 = self.parse(cell)?;
+                if !val.is_power_of_two() {
+                    return Err(self.corrupt(&format!("invalid tile `{val}`")));
+                }
+                board.set(r, c, Some(val));
+            }
+        }
+
+        Ok(Snapshot {
+            board,
+            score: self.parse(&score)?,
+            status,
+            moves: self.parse(&moves)?,
+            rng,
+        })
+    }
+}
@@ -0,0 +1,204 @@
+//! A local high-score table that outlives the process.
+//!
+//! Scores are appended to a plain text file, one finished game per line:
+//!
+//! ```text
+//! rust_2048 scores 1
+//! entry 20136 2048 1021 1874 1760812345 1234 4 4 false
+//! entry 7412 512 498 611 1760813020 99 5 5 true
+//! ```
+//!
+//! An entry holds the score, max tile, move count, duration in seconds, the time it was
+//! recorded in seconds since the Unix epoch, the seed, the board size and whether undo
+//! was used. Writers take an exclusive lock on the file, so several games finishing at
+//! once each add their line intact.
+
+use crate::{
+    game::{Game, GameResult},
+    save::{Reader, SaveError},
+};
+use std::{
+    env,
+    fs::{self, File, OpenOptions},
+    io::{self, prelude::*, BufReader, SeekFrom},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// The first word of every score file.
+const MAGIC: &str = "rust_2048 scores";
+/// The score file format version written by this build.
+pub const VERSION: u32 = 1;
+
+/// Environment variable that overrides where scores are kept.
+pub const PATH_VAR: &str = "RUST_2048_SCORES";
+
+/// One finished game.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Score {
+    pub result: GameResult,
+    /// Time spent playing.
+    pub duration: Duration,
+    /// When the game was recorded.
+    pub date: SystemTime,
+    pub seed: u64,
+    pub rows: usize,
+    pub cols: usize,
+}
+
+impl Score {
+    /// The game's result as it stands now, recorded at the current time.
+    pub fn new(game: &Game, duration: Duration) -> Self {
+        Self {
+            result: game.result(),
+            duration,
+            date: SystemTime::now(),
+            seed: game.seed(),
+            rows: game.board().rows(),
+            cols: game.board().cols(),
+        }
+    }
+
+    /// The UTC day the game was recorded, as `YYYY-MM-DD`.
+    pub fn day(&self) -> String {
+        let days = unix_secs(self.date).div_euclid(86_400) as i64;
+        // Howard Hinnant's days-to-civil algorithm.
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z.rem_euclid(146_097);
+        let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = yoe + era * 400 + i64::from(month <= 2);
+        format!("{year:04}-{month:02}-{day:02}")
+    }
+}
+
+fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs())
+}
+
+/// Every recorded game, best first.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Leaderboard {
+    pub scores: Vec<Score>,
+}
+
+impl Leaderboard {
+    /// Where scores are kept: `$RUST_2048_SCORES` if set, otherwise `rust_2048/scores`
+    /// under the XDG data directory. `None` if neither that nor `$HOME` is set.
+    pub fn default_path() -> Option<PathBuf> {
+        if let Some(path) = env::var_os(PATH_VAR).filter(|path| !path.is_empty()) {
+            return Some(PathBuf::from(path));
+        }
+        let data = env::var_os("XDG_DATA_HOME")
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".local/share")))?;
+        Some(data.join("rust_2048").join("scores"))
+    }
+
+    /// The best `n` scores.
+    pub fn top(&self, n: usize) -> &[Score] {
+        &self.scores[..n.min(self.scores.len())]
+    }
+
+    pub fn save<W: Write>(&self, mut out: W) -> io::Result<()> {
+        writeln!(out, "{MAGIC} {VERSION}")?;
+        for score in &self.scores {
+            write_entry(&mut out, score)?;
+        }
+        out.flush()
+    }
+
+    /// Read a table written by [`Leaderboard::save`] or [`Leaderboard::record`].
+    pub fn load<R: BufRead>(input: R) -> Result<Leaderboard, SaveError> {
+        let mut reader = Reader::new(input);
+        reader.header(MAGIC, 1..=VERSION)?;
+        let mut scores = Vec::new();
+        while let Some(line) = reader.try_next_line()? {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let [score, max_tile, moves, secs, date, seed, rows, cols, undo_used] =
+                reader.split::<String, 9>(&line, "entry")?;
+            scores.push(Score {
+                result: GameResult {
+                    score: reader.parse(&score)?,
+                    max_tile: reader.parse(&max_tile)?,
+                    moves: reader.parse(&moves)?,
+                    undo_used: reader.parse(&undo_used)?,
+                },
+                duration: Duration::from_secs(reader.parse(&secs)?),
+                date: UNIX_EPOCH + Duration::from_secs(reader.parse(&date)?),
+                seed: reader.parse(&seed)?,
+                rows: reader.parse(&rows)?,
+                cols: reader.parse(&cols)?,
+            });
+        }
+        // Ties go to whoever got there first.
+        scores.sort_by_key(|score| (std::cmp::Reverse(score.result.score), score.date));
+        Ok(Leaderboard { scores })
+    }
+
+    /// Read the table at `path`; a missing or empty file is an empty table.
+    pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<Leaderboard, SaveError> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => return Err(err.into()),
+        };
+        file.lock_shared()?;
+        // A writer may have created the file without having written to it yet.
+        if file.metadata()?.len() == 0 {
+            return Ok(Self::default());
+        }
+        Leaderboard::load(BufReader::new(file))
+    }
+
+    /// Add `score` to the table at `path`, creating the file and its directory if needed.
+    pub fn record<P: AsRef<Path>>(path: P, score: &Score) -> Result<(), SaveError> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(path)?;
+        // Held until `file` is dropped, so concurrent writers take turns.
+        file.lock()?;
+        let mut entry = Vec::new();
+        if file.seek(SeekFrom::End(0))? == 0 {
+            writeln!(entry, "{MAGIC} {VERSION}")?;
+        } else {
+            file.seek(SeekFrom::Start(0))?;
+            let mut reader = Reader::new(BufReader::new(&file));
+            reader.header(MAGIC, 1..=VERSION)?;
+        }
+        write_entry(&mut entry, score)?;
+        file.write_all(&entry)?;
+        Ok(())
+    }
+}
+
+fn write_entry<W: Write>(out: &mut W, score: &Score) -> io::Result<()> {
+    let result = &score.result;
+    writeln!(
+        out,
+        "entry {} {} {} {} {} {} {} {} {}",
+        result.score,
+        result.max_tile,
+        result.moves,
+        score.duration.as_secs(),
+        unix_secs(score.date),
+        score.seed,
+        score.rows,
+        score.cols,
+        result.undo_used
+    )
+}
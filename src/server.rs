@@ -0,0 +1,301 @@
+//! A game server speaking JSON over TCP or a Unix socket.
+//!
+//! Each connection sends one request per line and gets one response per line, in order.
+//! Sessions belong to the server rather than the connection, so one client can create a
+//! game and another watch it. A session keeps its game until it is closed or the server
+//! stops.
+//!
+//! ```text
+//! {"cmd":"new","seed":7,"rows":4,"cols":4}
+//! {"ok":true,"session":1,"state":{"board":[[0,0,2,0],...],"can_undo":false,...}}
+//! {"cmd":"move","session":1,"direction":"left"}
+//! {"gained":0,"moved":true,"ok":true,"session":1,"spawns":[[3,1,2]],"state":{...}}
+//! ```
+//!
+//! The commands are:
+//!
+//! - `new`, with optional `seed`, `rows`, `cols`, `target` and `undo_limit`, starts a
+//!   session; anything left out comes from the server's [`GameConfig`].
+//! - `move` plays `direction` (`up`, `down`, `left` or `right`) in `session`.
+//! - `undo` takes back the last move in `session`.
+//! - `state` returns the position of `session` without changing it.
+//! - `close` ends `session`.
+//! - `list` returns the ids of every open session.
+//!
+//! Spawns are `[row, col, value]`. A request that fails gets
+//! `{"error":"...","ok":false}` and changes nothing.
+
+use crate::{
+    board::{MAX_SIZE, MIN_SIZE},
+    game::{Direction, Event, Game, GameConfig, GameStatus},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+    collections::BTreeMap,
+    io::{self, prelude::*, BufReader},
+    net::{TcpListener, TcpStream},
+    os::unix::net::{UnixListener, UnixStream},
+    sync::{Arc, Mutex},
+    thread,
+};
+
+/// A request line, told apart by its `cmd` field.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(tag = "cmd", rename_all = "lowercase", deny_unknown_fields)]
+pub enum Request {
+    New {
+        seed: Option<u64>,
+        rows: Option<usize>,
+        cols: Option<usize>,
+        target: Option<u32>,
+        undo_limit: Option<u32>,
+    },
+    Move {
+        session: u64,
+        direction: String,
+    },
+    Undo {
+        session: u64,
+    },
+    State {
+        session: u64,
+    },
+    Close {
+        session: u64,
+    },
+    List,
+}
+
+/// A game's position as sent to clients.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct State {
+    /// Rows of cell values, 0 for empty.
+    pub board: Vec<Vec<u32>>,
+    pub score: u32,
+    pub moves: u32,
+    /// `playing`, `won` or `lost`.
+    pub status: String,
+    pub seed: u64,
+    pub target: u32,
+    pub can_undo: bool,
+}
+
+impl State {
+    pub fn of(game: &Game) -> Self {
+        Self {
+            board: game
+                .board()
+                .iter_rows()
+                .map(|row| row.iter().map(|val| val.unwrap_or(0)).collect())
+                .collect(),
+            score: game.score(),
+            moves: game.moves(),
+            status: match game.status() {
+                GameStatus::Continue => "playing",
+                GameStatus::Win => "won",
+                GameStatus::Lost => "lost",
+            }
+            .to_string(),
+            seed: game.seed(),
+            target: game.target(),
+            can_undo: game.can_undo(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Sessions {
+    next_id: u64,
+    /// Each game has its own lock so a slow move, as with an evil spawner, holds up
+    /// only its own session.
+    games: BTreeMap<u64, Arc<Mutex<Game>>>,
+}
+
+/// The sessions shared by every connection. Cloning gives another handle to the same
+/// sessions.
+#[derive(Clone)]
+pub struct Server {
+    config: GameConfig,
+    sessions: Arc<Mutex<Sessions>>,
+}
+
+impl Server {
+    /// A server whose new games are set up by `config` unless a request says otherwise.
+    pub fn new(config: GameConfig) -> Self {
+        Self {
+            config,
+            sessions: Arc::default(),
+        }
+    }
+
+    /// Accept TCP connections forever, serving each on its own thread. Connections that
+    /// fail to open are passed to `on_error` and skipped.
+    pub fn serve_tcp(
+        &self,
+        listener: TcpListener,
+        on_error: impl FnMut(io::Error),
+    ) -> io::Result<()> {
+        self.accept(listener.incoming(), TcpStream::try_clone, on_error)
+    }
+
+    /// Accept Unix socket connections forever, serving each on its own thread.
+    /// Connections that fail to open are passed to `on_error` and skipped.
+    pub fn serve_unix(
+        &self,
+        listener: UnixListener,
+        on_error: impl FnMut(io::Error),
+    ) -> io::Result<()> {
+        self.accept(listener.incoming(), UnixStream::try_clone, on_error)
+    }
+
+    /// Serve every connection from `incoming` on its own thread, reading from a second
+    /// handle made by `try_clone`.
+    fn accept<S: Read + Write + Send + 'static>(
+        &self,
+        incoming: impl Iterator<Item = io::Result<S>>,
+        try_clone: impl Fn(&S) -> io::Result<S>,
+        mut on_error: impl FnMut(io::Error),
+    ) -> io::Result<()> {
+        for stream in incoming {
+            let (reader, writer) = match stream.and_then(|stream| Ok((try_clone(&stream)?, stream)))
+            {
+                Ok(halves) => halves,
+                Err(err) => {
+                    on_error(err);
+                    continue;
+                }
+            };
+            let server = self.clone();
+            thread::spawn(move || server.serve(BufReader::new(reader), writer));
+        }
+        Ok(())
+    }
+
+    /// Answer requests from `input` on `output` until the input ends.
+    pub fn serve<R: BufRead, W: Write>(&self, input: R, mut output: W) -> io::Result<()> {
+        for line in input.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            writeln!(output, "{}", self.respond(&line))?;
+            output.flush()?;
+        }
+        Ok(())
+    }
+
+    /// The response to one request line.
+    pub fn respond(&self, line: &str) -> Value {
+        let response = serde_json::from_str(line)
+            .map_err(|err| format!("bad request: {err}"))
+            .and_then(|request| self.handle(request));
+        match response {
+            Ok(mut response) => {
+                response["ok"] = json!(true);
+                response
+            }
+            Err(err) => json!({ "ok": false, "error": err }),
+        }
+    }
+
+    fn handle(&self, request: Request) -> Result<Value, String> {
+        match request {
+            Request::New {
+                seed,
+                rows,
+                cols,
+                target,
+                undo_limit,
+            } => {
+                let config = GameConfig {
+                    seed: seed.or(self.config.seed),
+                    rows: rows.unwrap_or(self.config.rows),
+                    cols: cols.unwrap_or(self.config.cols),
+                    target: target.unwrap_or(self.config.target),
+                    undo_limit: undo_limit.or(self.config.undo_limit),
+                    spawn: self.config.spawn.clone(),
+                };
+                for size in [config.rows, config.cols] {
+                    if !(MIN_SIZE..=MAX_SIZE).contains(&size) {
+                        return Err(format!(
+                            "rows and cols must be from {MIN_SIZE} to {MAX_SIZE}"
+                        ));
+                    }
+                }
+                if !config.target.is_power_of_two() || config.target < 4 {
+                    return Err("target must be a power of two from 4 up".to_string());
+                }
+                let mut game = Game::with_config(config);
+                game.reset();
+                let state = State::of(&game);
+                let mut sessions = self.sessions.lock().unwrap();
+                sessions.next_id += 1;
+                let id = sessions.next_id;
+                sessions.games.insert(id, Arc::new(Mutex::new(game)));
+                Ok(json!({ "session": id, "state": state }))
+            }
+            Request::Move { session, direction } => {
+                let direction = Direction::from_name(&direction)
+                    .ok_or_else(|| format!("unknown direction `{direction}`"))?;
+                let game = self.game(session)?;
+                let mut game = game.lock().unwrap();
+                if game.status() != GameStatus::Continue {
+                    return Err("the game is over".to_string());
+                }
+                let (_, events) = game.move_events(direction);
+                let mut gained = 0;
+                let mut spawns = Vec::new();
+                for event in events.iter() {
+                    match *event {
+                        Event::Scored(points) => gained = points,
+                        Event::Spawned { at: (r, c), value } => spawns.push((r, c, value)),
+                        _ => {}
+                    }
+                }
+                Ok(json!({
+                    "session": session,
+                    "moved": !events.is_empty(),
+                    "gained": gained,
+                    "spawns": spawns,
+                    "state": State::of(&game),
+                }))
+            }
+            Request::Undo { session } => {
+                let game = self.game(session)?;
+                let mut game = game.lock().unwrap();
+                if !game.undo() {
+                    return Err("nothing to undo".to_string());
+                }
+                Ok(json!({ "session": session, "state": State::of(&game) }))
+            }
+            Request::State { session } => {
+                let game = self.game(session)?;
+                let game = game.lock().unwrap();
+                Ok(json!({ "session": session, "state": State::of(&game) }))
+            }
+            Request::Close { session } => {
+                let mut sessions = self.sessions.lock().unwrap();
+                sessions
+                    .games
+                    .remove(&session)
+                    .ok_or_else(|| format!("no session {session}"))?;
+                Ok(json!({ "session": session }))
+            }
+            Request::List => {
+                let sessions = self.sessions.lock().unwrap();
+                let ids: Vec<u64> = sessions.games.keys().copied().collect();
+                Ok(json!({ "sessions": ids }))
+            }
+        }
+    }
+
+    fn game(&self, session: u64) -> Result<Arc<Mutex<Game>>, String> {
+        let sessions = self.sessions.lock().unwrap();
+        sessions
+            .games
+            .get(&session)
+            .cloned()
+            .ok_or_else(|| format!("no session {session}"))
+    }
+}
@@ -0,0 +1,106 @@
+//! What the terminal can display, guessed from the environment, and when its window
+//! changes size.
+
+use std::{
+    env, mem, ptr,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// Set by the `SIGWINCH` handler.
+static RESIZED: AtomicBool = AtomicBool::new(false);
+
+/// How many colours escape codes may use.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorMode {
+    /// 24-bit colour.
+    TrueColor,
+    /// The xterm 256-colour palette.
+    Ansi256,
+    /// The 16 basic colours, whatever the terminal makes of them.
+    Ansi16,
+    /// No colour at all; bold and other attributes remain.
+    Plain,
+}
+
+impl ColorMode {
+    /// Parse a `--color` value; `None` for `auto` or anything unknown.
+    pub fn parse(name: &str) -> Option<Option<ColorMode>> {
+        let mode = match name {
+            "auto" => None,
+            "truecolor" | "24bit" => Some(ColorMode::TrueColor),
+            "256" => Some(ColorMode::Ansi256),
+            "16" => Some(ColorMode::Ansi16),
+            "none" => Some(ColorMode::Plain),
+            _ => return None,
+        };
+        Some(mode)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Capabilities {
+    pub color: ColorMode,
+    /// Whether box-drawing characters can be shown.
+    pub unicode: bool,
+}
+
+impl Capabilities {
+    /// Guess from `NO_COLOR`, `COLORTERM`, `TERM` and the locale.
+    pub fn detect() -> Self {
+        Self::detect_with(|name| env::var(name).unwrap_or_default())
+    }
+
+    /// Guess from environment variables as read by `var`, empty when unset.
+    pub(crate) fn detect_with(var: impl Fn(&str) -> String) -> Self {
+        let term = var("TERM");
+        let color = if !var("NO_COLOR").is_empty() || term == "dumb" {
+            ColorMode::Plain
+        } else if matches!(var("COLORTERM").as_str(), "truecolor" | "24bit") {
+            ColorMode::TrueColor
+        } else if term.contains("256color") {
+            ColorMode::Ansi256
+        } else {
+            ColorMode::Ansi16
+        };
+        // The first of these that is set decides the character set, as in setlocale. With
+        // none set, assume the terminal is a modern one.
+        let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+            .into_iter()
+            .map(&var)
+            .find(|value| !value.is_empty())
+            .map(|value| value.to_ascii_lowercase());
+        let unicode = term != "dumb"
+            && locale.is_none_or(|locale| locale.contains("utf-8") || locale.contains("utf8"));
+        Self { color, unicode }
+    }
+}
+
+extern "C" fn on_resize(_: libc::c_int) {
+    RESIZED.store(true, Ordering::Relaxed);
+}
+
+/// Start listening for `SIGWINCH`, so that [`resized`] notices the window changing size.
+pub fn watch_resize() {
+    // SAFETY: the handler only stores to an atomic, which is async-signal-safe, and
+    // SA_RESTART keeps blocking reads from failing when the signal arrives.
+    unsafe {
+        let mut action: libc::sigaction = mem::zeroed();
+        action.sa_sigaction = on_resize as extern "C" fn(libc::c_int) as libc::sighandler_t;
+        action.sa_flags = libc::SA_RESTART;
+        libc::sigemptyset(&mut action.sa_mask);
+        libc::sigaction(libc::SIGWINCH, &action, ptr::null_mut());
+    }
+}
+
+/// Whether the window has changed size since the last call.
+pub fn resized() -> bool {
+    RESIZED.swap(false, Ordering::Relaxed)
+}
+
+/// The window's size in columns and rows, or 80x24 if it cannot be found.
+pub fn size() -> (u16, u16) {
+    termion::terminal_size()
+        .ok()
+        .filter(|&(cols, rows)| cols > 0 && rows > 0)
+        .unwrap_or((80, 24))
+}
@@ -1,2 +1,24 @@
 #[cfg(test)]
+mod ai_test;
+#[cfg(test)]
+mod bitboard_test;
+#[cfg(test)]
+mod board_test;
+#[cfg(test)]
+mod broadcast_test;
+#[cfg(test)]
+mod env_test;
+#[cfg(test)]
 mod game_test;
+#[cfg(test)]
+mod protocol_test;
+#[cfg(test)]
+mod race_test;
+#[cfg(test)]
+mod replay_test;
+#[cfg(test)]
+mod save_test;
+#[cfg(test)]
+mod scores_test;
+#[cfg(test)]
+mod server_test;
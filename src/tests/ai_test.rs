@@ -0,0 +1,127 @@
+use crate::{
+    ai::{self, Expectimax, Greedy, Player, PlayerKind, Random, Weighted},
+    board::Board,
+    game::{Direction, Game, GameConfig, GameStatus},
+};
+
+fn board(rows: &[[u32; 4]]) -> Board {
+    let mut board = Board::new(rows.len(), 4);
+    for (r, row) in rows.iter().enumerate() {
+        for (c, &val) in row.iter().enumerate() {
+            board.set(r, c, if val == 0 { None } else { Some(val) });
+        }
+    }
+    board
+}
+
+#[test]
+fn features() {
+    let sorted = board(&[[16, 8, 4, 2], [8, 4, 2, 0], [4, 2, 0, 0], [2, 0, 0, 0]]);
+    assert_eq!(ai::empty_cells(&sorted), 6.0);
+    assert_eq!(ai::monotonicity(&sorted), 0.0);
+    assert_eq!(ai::corner(&sorted), 4.0);
+    assert_eq!(ai::smoothness(&sorted), -12.0);
+
+    let zigzag = board(&[[2, 16, 2, 16], [0; 4], [0; 4], [0; 4]]);
+    assert_eq!(ai::monotonicity(&zigzag), -3.0);
+    assert_eq!(ai::corner(&zigzag), 4.0);
+}
+
+#[test]
+fn only_legal_move() {
+    let stuck_but_left = board(&[[2, 4, 2, 0], [4, 2, 4, 2], [2, 4, 2, 4], [4, 2, 4, 2]]);
+    let odds = [(2, 0.9), (4, 0.1)];
+    let expectimax = Expectimax::default();
+    assert!(matches!(
+        expectimax.best_move_on(&stuck_but_left, &odds),
+        Some(Direction::Right | Direction::Up)
+    ));
+
+    let stuck = board(&[[2, 4, 2, 4], [4, 2, 4, 2], [2, 4, 2, 4], [4, 2, 4, 2]]);
+    assert_eq!(expectimax.best_move_on(&stuck, &odds), None);
+}
+
+#[test]
+fn largest_tiles_merge() {
+    // Only the two 32768s can move, which a bitboard could not merge.
+    let mut game = Game::new();
+    game.board = board(&[
+        [32768, 32768, 2, 4],
+        [2, 4, 8, 16],
+        [4, 8, 16, 2],
+        [8, 16, 2, 4],
+    ]);
+    assert_eq!(game.legal_moves(), [Direction::Left, Direction::Right]);
+    let best = Expectimax::default().best_move(&game);
+    assert!(matches!(best, Some(Direction::Left | Direction::Right)));
+}
+
+#[test]
+fn pluggable_heuristic() {
+    // Only empty cells count, so the AI always merges the pair.
+    let expectimax = Expectimax::new(1, ai::empty_cells::<Board>);
+    let pair = board(&[[0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 2], [0, 0, 0, 2]]);
+    assert!(matches!(
+        expectimax.best_move_on(&pair, &[(2, 1.0)]),
+        Some(Direction::Up | Direction::Down)
+    ));
+}
+
+#[test]
+fn plays_a_game() {
+    let mut game = Game::with_config(GameConfig {
+        seed: Some(1),
+        ..GameConfig::default()
+    });
+    game.reset();
+    let expectimax = Expectimax::new(2, Weighted::default());
+    while let Some(direction) = expectimax.best_move(&game) {
+        if game.move_board(direction).0 != GameStatus::Continue {
+            break;
+        }
+    }
+    assert!(game.board().max_tile() >= 512, "{:?}", game.board());
+}
+
+#[test]
+fn baseline_players() {
+    let mut game = Game::with_config(GameConfig {
+        seed: Some(5),
+        ..GameConfig::default()
+    });
+    game.reset();
+    game.board = board(&[[2, 2, 0, 0], [4, 0, 0, 0], [8, 0, 0, 0], [4, 0, 0, 0]]);
+    // Only a horizontal move merges the pair.
+    assert!(matches!(
+        Greedy.choose(&game),
+        Some(Direction::Left | Direction::Right)
+    ));
+
+    let mut random = Random::new(7);
+    for _ in 0..20 {
+        let direction = random.choose(&game).unwrap();
+        assert!(game.is_legal(direction));
+    }
+
+    game.board = board(&[[2, 4, 2, 4], [4, 2, 4, 2], [2, 4, 2, 4], [4, 2, 4, 2]]);
+    for (_, kind) in PlayerKind::ALL {
+        assert_eq!(kind.player(2, 1).choose(&game), None);
+    }
+    assert_eq!(PlayerKind::from_name("greedy"), Some(PlayerKind::Greedy));
+    assert_eq!(PlayerKind::from_name("smart"), None);
+}
+
+#[test]
+fn worst_spawn() {
+    // A 2 in the corner can merge upwards; a 4 leaves no move.
+    let nearly_full = board(&[[2, 4, 2, 4], [4, 2, 4, 2], [2, 4, 2, 4], [0, 8, 16, 8]]);
+    assert_eq!(ai::worst_spawn(&nearly_full, &[2, 4], 1), Some(((3, 0), 4)));
+    assert_eq!(ai::worst_spawn(&nearly_full, &[2], 2), Some(((3, 0), 2)));
+
+    let full = board(&[[2, 4, 2, 4], [4, 2, 4, 2], [2, 4, 2, 4], [4, 2, 4, 2]]);
+    assert_eq!(ai::worst_spawn(&full, &[2, 4], 1), None);
+
+    // An open board is searched less deep than asked, rather than for minutes.
+    let open = Board::new(16, 16);
+    assert!(ai::worst_spawn(&open, &[2, 4], 4).is_some());
+}
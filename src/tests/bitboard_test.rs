@@ -0,0 +1,82 @@
+use crate::{
+    bitboard::BitBoard,
+    board::{Board, Grid},
+    game::Direction,
+};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+fn random_board(rng: &mut ChaCha8Rng) -> Board {
+    let mut board = Board::new(4, 4);
+    let fill = rng.gen_range(0.0..1.0);
+    for r in 0..4 {
+        for c in 0..4 {
+            if rng.gen_bool(fill) {
+                // Small exponents make merges common; the odd large one checks the
+                // nibble boundaries.
+                let exp = if rng.gen_bool(0.9) {
+                    rng.gen_range(1..=4)
+                } else {
+                    rng.gen_range(1..=14)
+                };
+                board.set(r, c, Some(1 << exp));
+            }
+        }
+    }
+    board
+}
+
+#[test]
+fn round_trip() {
+    let mut rng = ChaCha8Rng::seed_from_u64(0);
+    for _ in 0..100 {
+        let board = random_board(&mut rng);
+        let packed = BitBoard::from_board(&board).unwrap();
+        assert_eq!(packed.to_board(), board);
+        for r in 0..4 {
+            for c in 0..4 {
+                assert_eq!(Grid::get(&packed, r, c), board.get(r, c));
+            }
+        }
+        assert_eq!(Grid::empty_cells(&packed), board.empty_cells());
+        assert_eq!(Grid::max_tile(&packed), board.max_tile());
+    }
+
+    assert_eq!(BitBoard::from_board(&Board::new(4, 5)), None);
+    let mut board = Board::new(4, 4);
+    board.set(0, 0, Some(1 << 16));
+    assert_eq!(BitBoard::from_board(&board), None);
+    // 32768 fits, but two of them would merge on the board and not here.
+    board.set(0, 0, Some(1 << 15));
+    assert_eq!(BitBoard::from_board(&board), None);
+}
+
+#[test]
+fn moves_match_board() {
+    let mut rng = ChaCha8Rng::seed_from_u64(1);
+    for _ in 0..20_000 {
+        let board = random_board(&mut rng);
+        let packed = BitBoard::from_board(&board).unwrap();
+        assert_eq!(Grid::is_stuck(&packed), board.is_stuck(), "{board:?}");
+        for direction in Direction::ALL {
+            let (mut expected, mut actual) = (board.clone(), packed);
+            assert_eq!(
+                Grid::shift(&mut actual, direction),
+                expected.shift(direction),
+                "{direction:?} on {board:?}"
+            );
+            assert_eq!(actual.to_board(), expected, "{direction:?} on {board:?}");
+        }
+    }
+}
+
+#[test]
+fn largest_tiles_do_not_merge() {
+    let mut packed = BitBoard::default();
+    packed.set(0, 0, Some(16384));
+    packed.set(0, 1, Some(16384));
+    assert_eq!(packed.shift(Direction::Left), (32768, true));
+    packed.set(0, 1, Some(32768));
+    assert_eq!(packed.shift(Direction::Left), (0, false));
+    assert_eq!(packed.get(0, 1), Some(32768));
+}
@@ -0,0 +1,221 @@
+use crate::board::Board;
+use crate::game::{Direction, Event};
+
+fn set_row(board: &mut Board, row_n: usize, row: [Option<u32>; 4]) {
+    for (c, val) in row.into_iter().enumerate() {
+        board.set(row_n, c, val);
+    }
+}
+
+fn set_col(board: &mut Board, col_n: usize, col: [Option<u32>; 4]) {
+    for (r, val) in col.into_iter().enumerate() {
+        board.set(r, col_n, val);
+    }
+}
+
+fn get_col(board: &mut Board, col_n: usize) -> [Option<u32>; 4] {
+    let mut result = [None; 4];
+    for (r, val) in result.iter_mut().enumerate() {
+        *val = board.get(r, col_n);
+    }
+    result
+}
+
+#[test]
+fn move_left() {
+    let mut board = Board::new(4, 4);
+
+    set_row(&mut board, 0, [Some(2), Some(2), Some(2), Some(2)]);
+    assert_eq!(board.shift(Direction::Left), (8, true));
+    assert_eq!(board.max_tile(), 4);
+    assert_eq!(board.row(0), [Some(4), Some(4), None, None]);
+
+    set_row(&mut board, 0, [Some(2), None, None, Some(2)]);
+    assert_eq!(board.shift(Direction::Left), (4, true));
+    assert_eq!(board.max_tile(), 4);
+    assert_eq!(board.row(0), [Some(4), None, None, None]);
+
+    set_row(&mut board, 0, [Some(2), Some(2), Some(4), Some(4)]);
+    assert_eq!(board.shift(Direction::Left), (12, true));
+    assert_eq!(board.max_tile(), 8);
+    assert_eq!(board.row(0), [Some(4), Some(8), None, None]);
+
+    set_row(&mut board, 0, [Some(2), None, Some(2), Some(4)]);
+    assert_eq!(board.shift(Direction::Left), (4, true));
+    assert_eq!(board.max_tile(), 4);
+    assert_eq!(board.row(0), [Some(4), Some(4), None, None]);
+
+    set_row(&mut board, 0, [Some(2), Some(4), Some(8), Some(16)]);
+    assert_eq!(board.shift(Direction::Left), (0, false));
+    assert_eq!(board.max_tile(), 16);
+    assert_eq!(board.row(0), [Some(2), Some(4), Some(8), Some(16)]);
+}
+
+#[test]
+fn move_right() {
+    let mut board = Board::new(4, 4);
+
+    set_row(&mut board, 0, [Some(2), Some(2), Some(2), Some(2)]);
+    assert_eq!(board.shift(Direction::Right), (8, true));
+    assert_eq!(board.max_tile(), 4);
+    assert_eq!(board.row(0), [None, None, Some(4), Some(4)]);
+
+    set_row(&mut board, 0, [Some(2), None, None, Some(2)]);
+    assert_eq!(board.shift(Direction::Right), (4, true));
+    assert_eq!(board.max_tile(), 4);
+    assert_eq!(board.row(0), [None, None, None, Some(4)]);
+
+    set_row(&mut board, 0, [Some(4), Some(4), Some(2), Some(2)]);
+    assert_eq!(board.shift(Direction::Right), (12, true));
+    assert_eq!(board.max_tile(), 8);
+    assert_eq!(board.row(0), [None, None, Some(8), Some(4)]);
+
+    set_row(&mut board, 0, [Some(2), None, Some(2), Some(4)]);
+    assert_eq!(board.shift(Direction::Right), (4, true));
+    assert_eq!(board.max_tile(), 4);
+    assert_eq!(board.row(0), [None, None, Some(4), Some(4)]);
+
+    set_row(&mut board, 0, [Some(2), Some(4), Some(8), Some(16)]);
+    assert_eq!(board.shift(Direction::Right), (0, false));
+    assert_eq!(board.max_tile(), 16);
+    assert_eq!(board.row(0), [Some(2), Some(4), Some(8), Some(16)]);
+}
+
+#[test]
+fn move_up() {
+    let mut board = Board::new(4, 4);
+
+    set_col(&mut board, 0, [Some(2), Some(2), Some(2), Some(2)]);
+    assert_eq!(board.shift(Direction::Up), (8, true));
+    assert_eq!(board.max_tile(), 4);
+    assert_eq!(get_col(&mut board, 0), [Some(4), Some(4), None, None]);
+
+    set_col(&mut board, 0, [Some(2), None, None, Some(2)]);
+    assert_eq!(board.shift(Direction::Up), (4, true));
+    assert_eq!(board.max_tile(), 4);
+    assert_eq!(get_col(&mut board, 0), [Some(4), None, None, None]);
+
+    set_col(&mut board, 0, [Some(2), Some(2), Some(4), Some(4)]);
+    assert_eq!(board.shift(Direction::Up), (12, true));
+    assert_eq!(board.max_tile(), 8);
+    assert_eq!(get_col(&mut board, 0), [Some(4), Some(8), None, None]);
+
+    set_col(&mut board, 0, [Some(2), None, Some(2), Some(4)]);
+    assert_eq!(board.shift(Direction::Up), (4, true));
+    assert_eq!(board.max_tile(), 4);
+    assert_eq!(get_col(&mut board, 0), [Some(4), Some(4), None, None]);
+
+    set_col(&mut board, 0, [Some(2), Some(4), Some(8), Some(16)]);
+    assert_eq!(board.shift(Direction::Up), (0, false));
+    assert_eq!(board.max_tile(), 16);
+    assert_eq!(
+        get_col(&mut board, 0),
+        [Some(2), Some(4), Some(8), Some(16)]
+    );
+}
+
+#[test]
+fn move_down() {
+    let mut board = Board::new(4, 4);
+
+    set_col(&mut board, 0, [Some(2), Some(2), Some(2), Some(2)]);
+    assert_eq!(board.shift(Direction::Down), (8, true));
+    assert_eq!(board.max_tile(), 4);
+    assert_eq!(get_col(&mut board, 0), [None, None, Some(4), Some(4)]);
+
+    set_col(&mut board, 0, [Some(2), None, None, Some(2)]);
+    assert_eq!(board.shift(Direction::Down), (4, true));
+    assert_eq!(board.max_tile(), 4);
+    assert_eq!(get_col(&mut board, 0), [None, None, None, Some(4)]);
+
+    set_col(&mut board, 0, [Some(4), Some(4), Some(2), Some(2)]);
+    assert_eq!(board.shift(Direction::Down), (12, true));
+    assert_eq!(board.max_tile(), 8);
+    assert_eq!(get_col(&mut board, 0), [None, None, Some(8), Some(4)]);
+
+    set_col(&mut board, 0, [Some(2), None, Some(2), Some(4)]);
+    assert_eq!(board.shift(Direction::Down), (4, true));
+    assert_eq!(board.max_tile(), 4);
+    assert_eq!(get_col(&mut board, 0), [None, None, Some(4), Some(4)]);
+
+    set_col(&mut board, 0, [Some(2), Some(4), Some(8), Some(16)]);
+    assert_eq!(board.shift(Direction::Down), (0, false));
+    assert_eq!(board.max_tile(), 16);
+    assert_eq!(
+        get_col(&mut board, 0),
+        [Some(2), Some(4), Some(8), Some(16)]
+    );
+}
+
+#[test]
+fn non_square() {
+    let mut board = Board::new(2, 5);
+    board.set(0, 0, Some(2));
+    board.set(0, 4, Some(2));
+    board.set(1, 2, Some(4));
+
+    assert_eq!(board.shift(Direction::Right), (4, true));
+    assert_eq!(board.row(0), [None, None, None, None, Some(4)]);
+    assert_eq!(board.row(1), [None, None, None, None, Some(4)]);
+
+    assert_eq!(board.shift(Direction::Down), (8, true));
+    assert_eq!(board.row(0), [None; 5]);
+    assert_eq!(board.row(1), [None, None, None, None, Some(8)]);
+    assert_eq!(board.empty_cells().len(), 9);
+}
+
+#[test]
+fn is_stuck() {
+    let mut board = Board::new(2, 3);
+    for (i, val) in [2, 4, 8, 16, 32, 64].into_iter().enumerate() {
+        board.set(i / 3, i % 3, Some(val));
+    }
+    assert!(board.is_stuck());
+
+    board.set(1, 2, Some(8));
+    assert!(!board.is_stuck());
+
+    board.set(1, 2, None);
+    assert!(!board.is_stuck());
+}
+
+#[test]
+fn shift_events() {
+    let mut board = Board::new(4, 4);
+    set_row(&mut board, 1, [Some(2), Some(2), None, Some(8)]);
+    set_row(&mut board, 3, [Some(8), None, None, None]);
+
+    let mut events = Vec::new();
+    assert_eq!(board.shift_events(Direction::Left, &mut events), (4, true));
+    assert_eq!(
+        events,
+        [
+            Event::Moved {
+                from: (1, 1),
+                to: (1, 0),
+                value: 2
+            },
+            Event::Merged {
+                at: (1, 0),
+                value: 4
+            },
+            Event::Moved {
+                from: (1, 3),
+                to: (1, 1),
+                value: 8
+            },
+        ]
+    );
+
+    events.clear();
+    assert_eq!(board.shift_events(Direction::Left, &mut events), (0, false));
+    assert!(events.is_empty());
+}
+
+#[test]
+fn display() {
+    let mut board = Board::new(2, 4);
+    set_row(&mut board, 0, [Some(2), None, Some(128), None]);
+    set_row(&mut board, 1, [None, Some(16), None, Some(4)]);
+    assert_eq!(board.to_string(), "  2   . 128   .\n  .  16   .   4\n");
+}
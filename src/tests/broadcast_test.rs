@@ -0,0 +1,76 @@
+use crate::{
+    broadcast::{Broadcaster, Spectator},
+    game::{Direction, Game, GameConfig},
+    server::State,
+};
+use std::{
+    net::TcpListener,
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Wait up to a few seconds for `done` to hold.
+fn eventually(mut done: impl FnMut() -> bool) {
+    let start = Instant::now();
+    while !done() {
+        assert!(start.elapsed() < Duration::from_secs(5), "timed out");
+        thread::sleep(Duration::from_millis(5));
+    }
+}
+
+/// The next state the spectator receives.
+fn next(spectator: &mut Spectator) -> State {
+    let mut state = None;
+    eventually(|| {
+        state = spectator.latest();
+        state.is_some()
+    });
+    state.unwrap()
+}
+
+#[test]
+fn spectators_follow_the_game() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let broadcaster = Broadcaster::new(listener);
+    let mut game = Game::with_config(GameConfig {
+        seed: Some(9),
+        ..GameConfig::default()
+    });
+    game.reset();
+    broadcaster.publish(&game);
+
+    // One watches from the start.
+    let mut early = Spectator::connect(addr).unwrap();
+    assert_eq!(next(&mut early), State::of(&game));
+    eventually(|| broadcaster.spectators() == 1);
+    for direction in [Direction::Left, Direction::Up, Direction::Right] {
+        game.move_board(direction);
+        broadcaster.publish(&game);
+        assert_eq!(next(&mut early), State::of(&game));
+    }
+
+    // One joins mid-game and starts from the latest position.
+    let mut late = Spectator::connect(addr).unwrap();
+    assert_eq!(next(&mut late), State::of(&game));
+    eventually(|| broadcaster.spectators() == 2);
+
+    // Nothing is sent when nothing changed.
+    broadcaster.publish(&game);
+    game.move_board(Direction::Down);
+    broadcaster.publish(&game);
+    for spectator in [&mut early, &mut late] {
+        assert_eq!(next(spectator), State::of(&game));
+        thread::sleep(Duration::from_millis(20));
+        assert_eq!(spectator.latest(), None);
+    }
+
+    drop(late);
+    drop(broadcaster);
+    eventually(|| {
+        early.latest();
+        early.is_over()
+    });
+    // Nobody else can join once it is over.
+    assert!(Spectator::connect(addr).is_err());
+}
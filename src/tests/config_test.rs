@@ -0,0 +1,48 @@
+use crate::config::Config;
+use std::path::Path;
+
+#[test]
+fn sections() {
+    let text = "# top\nname = rose\n\n[keys]\n  up = k  Up \n[ theme ]\n# x = y\nname=dusk\n";
+    let config = Config::parse(Path::new("config"), text).unwrap();
+    let settings: Vec<_> = ["", "keys", "theme"]
+        .iter()
+        .flat_map(|name| config.section(name))
+        .map(|setting| {
+            (
+                setting.section.as_str(),
+                setting.name.as_str(),
+                setting.value.as_str(),
+                setting.line,
+            )
+        })
+        .collect();
+    assert_eq!(
+        settings,
+        [
+            ("", "name", "rose", 2),
+            ("keys", "up", "k  Up", 5),
+            ("theme", "name", "dusk", 8),
+        ]
+    );
+    let up = config.section("keys").next().unwrap();
+    assert_eq!(config.error(up, "bad"), "config:5: bad");
+}
+
+#[test]
+fn malformed_lines() {
+    for text in ["[keys]\nup k", "= k", "[keys"] {
+        let line = text.lines().count();
+        assert_eq!(
+            Config::parse(Path::new("config"), text).err().unwrap(),
+            format!("config:{line}: expected `[section]` or `name = value`"),
+            "{text}"
+        );
+    }
+}
+
+#[test]
+fn missing_file() {
+    let config = Config::load(Path::new("/nonexistent/rust_2048/config")).unwrap();
+    assert_eq!(config.section("keys").count(), 0);
+}
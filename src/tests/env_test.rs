@@ -0,0 +1,172 @@
+use crate::{
+    board::Board,
+    env::{Encoding, Env, VecEnv},
+    game::{Direction, Game, GameConfig, GameStatus, SpawnPolicy},
+};
+
+#[test]
+fn encodings() {
+    let config = GameConfig::default();
+    let mut raw = Env::new(config.clone(), Encoding::Raw);
+    let mut log2 = Env::new(config.clone(), Encoding::Log2);
+    let mut one_hot = Env::new(config, Encoding::OneHot { planes: 3 });
+    for env in [&mut raw, &mut log2, &mut one_hot] {
+        env.reset(7);
+        for direction in [
+            Direction::Left,
+            Direction::Up,
+            Direction::Left,
+            Direction::Up,
+        ] {
+            env.step(direction);
+        }
+    }
+    assert_eq!(raw.observation_shape(), [4, 4]);
+    assert_eq!(one_hot.observation_shape(), [3, 4, 4]);
+
+    let cells: Vec<u32> = raw
+        .game()
+        .board()
+        .iter_rows()
+        .flatten()
+        .map(|val| val.unwrap_or(0))
+        .collect();
+    let raw = raw.observation();
+    let log2 = log2.observation();
+    let one_hot = one_hot.observation();
+    assert_eq!(one_hot.len(), 48);
+    for (i, &val) in cells.iter().enumerate() {
+        assert_eq!(raw[i], val as f32);
+        let exponent = if val == 0 { 0 } else { val.ilog2() as usize };
+        assert_eq!(log2[i], exponent as f32);
+        // Tiles past 4 all land on the last plane.
+        let plane = exponent.min(2);
+        for p in 0..3 {
+            assert_eq!(one_hot[p * 16 + i], if p == plane { 1.0 } else { 0.0 });
+        }
+    }
+}
+
+#[test]
+#[should_panic = "the encodings cannot tell 1s from empty cells"]
+fn rejects_spawning_ones() {
+    let config = GameConfig {
+        spawn: SpawnPolicy {
+            weights: vec![(1, 1.0), (2, 1.0)],
+            ..SpawnPolicy::default()
+        },
+        ..GameConfig::default()
+    };
+    Env::new(config, Encoding::Log2);
+}
+
+#[test]
+fn steps_like_the_game() {
+    let config = GameConfig {
+        seed: Some(11),
+        ..GameConfig::default()
+    };
+    let mut game = Game::with_config(config.clone());
+    game.reset();
+    let mut env = Env::new(config, Encoding::Raw);
+    env.reset(11);
+    assert_eq!(env.game().board(), game.board());
+
+    let mut total = 0.0;
+    let mut steps = 0;
+    loop {
+        let mask = env.action_mask();
+        let Some(i) = mask.iter().position(|&legal| legal) else {
+            break;
+        };
+        let before = game.score();
+        game.move_board(Direction::ALL[i]);
+        let step = env.step(Direction::ALL[i]);
+        assert_eq!(step.reward, (game.score() - before) as f32);
+        assert!(step.info.moved);
+        assert_eq!(step.info.score, game.score());
+        assert_eq!(step.done, game.status() != GameStatus::Continue);
+        total += step.reward;
+        steps += 1;
+        if step.done {
+            assert_eq!(step.info.action_mask, [false; 4]);
+            break;
+        }
+    }
+    assert_eq!(total, env.game().score() as f32);
+    assert_eq!(steps, env.game().moves());
+}
+
+#[test]
+fn illegal_moves_do_nothing() {
+    let mut env = Env::new(GameConfig::default(), Encoding::Log2);
+    env.reset(3);
+    // Every tile is against the left edge, so moving left does nothing.
+    env.game.board = Board::new(4, 4);
+    env.game.board.set(0, 0, Some(2));
+    env.game.board.set(2, 0, Some(4));
+    let before = env.observation();
+    assert_eq!(env.action_mask(), [true, true, false, true]);
+    let step = env.step(Direction::Left);
+    assert!(!step.info.moved);
+    assert_eq!(step.reward, 0.0);
+    assert_eq!(step.observation, before);
+    assert_eq!(step.info.moves, 0);
+}
+
+#[test]
+fn vectorized() {
+    let config = GameConfig {
+        rows: 3,
+        cols: 3,
+        ..GameConfig::default()
+    };
+    let seeds: Vec<u64> = (0..5).collect();
+    let mut serial = VecEnv::new(5, config.clone(), Encoding::Log2);
+    let mut threaded = VecEnv::new(5, config.clone(), Encoding::Log2).with_threads(3);
+    assert_eq!(serial.reset(&seeds), threaded.reset(&seeds));
+
+    // Each environment matches one stepped on its own.
+    let mut single = Env::new(config, Encoding::Log2);
+    single.reset(2);
+
+    let mut finished = [false; 5];
+    for turn in 0..1000 {
+        let actions: Vec<Direction> = serial
+            .action_masks()
+            .iter()
+            .map(|mask| {
+                let legal = (turn..turn + 4).map(|i| i % 4).find(|&i| mask[i]);
+                Direction::ALL[legal.unwrap_or(0)]
+            })
+            .collect();
+        let batch = serial.step(&actions);
+        assert_eq!(batch, threaded.step(&actions));
+        assert_eq!(batch.observations.len(), 5 * 9);
+
+        let step = single.step(actions[2]);
+        if !finished[2] {
+            assert_eq!(batch.observations[18..27], step.observation);
+            assert_eq!(batch.rewards[2], step.reward);
+        }
+        for (i, &done) in batch.dones.iter().enumerate() {
+            if finished[i] {
+                // Finished environments stay put until reset.
+                assert!(done);
+                assert_eq!(batch.rewards[i], 0.0);
+                assert!(!batch.infos[i].moved);
+            }
+            finished[i] = done;
+        }
+    }
+    assert!(finished.iter().all(|&done| done));
+
+    let seeds: Vec<u64> = (10..15).collect();
+    let observations = serial.reset_done(&seeds);
+    assert_eq!(observations, threaded.reset_done(&seeds));
+    assert!(serial
+        .step(&[Direction::Up; 5])
+        .dones
+        .iter()
+        .all(|&done| !done));
+}
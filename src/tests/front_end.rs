@@ -0,0 +1,10 @@
+#[cfg(test)]
+mod config_test;
+#[cfg(test)]
+mod keymap_test;
+#[cfg(test)]
+mod terminal_test;
+#[cfg(test)]
+mod theme_test;
+#[cfg(test)]
+mod tui_test;
@@ -1,119 +1,299 @@
-use crate::game::Game;
+use crate::game::{Direction, Event, Game, GameConfig, GameStatus, SpawnPolicy, Spawner};
 
-fn set_row(game: &mut Game, row_n: usize, row: [Option<u32>; 4]) {
-    game.board[row_n] = row;
-}
+#[test]
+fn legal_moves() {
+    let mut game = Game::new();
+    assert!(game.legal_moves().is_empty());
 
-fn set_col(game: &mut Game, col_n: usize, col: [Option<u32>; 4]) {
-    for (r, val) in col.into_iter().enumerate() {
-        game.board[r][col_n] = val;
-    }
-}
+    game.board.set(0, 0, Some(2));
+    assert_eq!(game.legal_moves(), [Direction::Down, Direction::Right]);
 
-fn get_col(game: &mut Game, col_n: usize) -> [Option<u32>; 4] {
-    let mut result = [None; 4];
-    for r in 0..4 {
-        result[r] = game.board[r][col_n];
-    }
-    result
+    game.reset();
+    let tiles = 16 - game.board().empty_cells().len();
+    assert_eq!(tiles, 2);
+    assert_eq!(game.score(), 0);
+    assert_eq!(game.status(), GameStatus::Continue);
 }
 
 #[test]
-fn move_left() {
-    let mut game = Game::new();
-
-    set_row(&mut game, 0, [Some(2), Some(2), Some(2), Some(2)]);
-    assert_eq!(game.move_left(), (4, true));
-    assert_eq!(game.board[0], [Some(4), Some(4), None, None]);
-
-    set_row(&mut game, 0, [Some(2), None, None, Some(2)]);
-    assert_eq!(game.move_left(), (4, true));
-    assert_eq!(game.board[0], [Some(4), None, None, None]);
+fn configured_size() {
+    let mut game = Game::with_config(GameConfig {
+        rows: 4,
+        cols: 6,
+        ..GameConfig::default()
+    });
+    game.reset();
+    assert_eq!((game.board().rows(), game.board().cols()), (4, 6));
+    assert_eq!(game.board().empty_cells().len(), 22);
+}
 
-    set_row(&mut game, 0, [Some(2), Some(2), Some(4), Some(4)]);
-    assert_eq!(game.move_left(), (8, true));
-    assert_eq!(game.board[0], [Some(4), Some(8), None, None]);
+#[test]
+fn seeded_games_repeat() {
+    let config = GameConfig {
+        seed: Some(2048),
+        ..GameConfig::default()
+    };
+    let moves = [
+        Direction::Left,
+        Direction::Up,
+        Direction::Right,
+        Direction::Down,
+    ];
 
-    set_row(&mut game, 0, [Some(2), None, Some(2), Some(4)]);
-    assert_eq!(game.move_left(), (4, true));
-    assert_eq!(game.board[0], [Some(4), Some(4), None, None]);
+    let mut games = [Game::with_config(config.clone()), Game::with_config(config)];
+    for game in games.iter_mut() {
+        game.reset();
+    }
+    for direction in moves.into_iter().cycle().take(40) {
+        let spawns = games.each_mut().map(|game| game.move_board(direction));
+        assert_eq!(spawns[0], spawns[1]);
+        assert_eq!(games[0].board(), games[1].board());
+    }
 
-    set_row(&mut game, 0, [Some(2), Some(4), Some(8), Some(16)]);
-    assert_eq!(game.move_left(), (16, false));
-    assert_eq!(game.board[0], [Some(2), Some(4), Some(8), Some(16)]);
+    let first = games[0].board().clone();
+    games[0].reset();
+    games[1].reseed(2048);
+    assert_eq!(games[0].board(), games[1].board());
+    assert_eq!(games[0].score(), 0);
+    assert_ne!(games[0].board(), &first);
 }
 
 #[test]
-fn move_right() {
-    let mut game = Game::new();
+fn undo_redo() {
+    let mut game = Game::with_config(GameConfig {
+        seed: Some(7),
+        ..GameConfig::default()
+    });
+    game.reset();
+    assert!(!game.undo());
 
-    set_row(&mut game, 0, [Some(2), Some(2), Some(2), Some(2)]);
-    assert_eq!(game.move_right(), (4, true));
-    assert_eq!(game.board[0], [None, None, Some(4), Some(4)]);
+    let start = game.board().clone();
+    let (_, spawned) = game.move_board(game.legal_moves()[0]);
+    let after = game.board().clone();
+    let score = game.score();
+    assert_eq!(game.moves(), 1);
 
-    set_row(&mut game, 0, [Some(2), None, None, Some(2)]);
-    assert_eq!(game.move_right(), (4, true));
-    assert_eq!(game.board[0], [None, None, None, Some(4)]);
+    assert!(game.undo());
+    assert_eq!(game.board(), &start);
+    assert_eq!((game.score(), game.moves()), (0, 0));
+    assert!(game.undo_used());
 
-    set_row(&mut game, 0, [Some(4), Some(4), Some(2), Some(2)]);
-    assert_eq!(game.move_right(), (8, true));
-    assert_eq!(game.board[0], [None, None, Some(8), Some(4)]);
+    assert!(game.redo());
+    assert_eq!(game.board(), &after);
+    assert_eq!((game.score(), game.moves()), (score, 1));
+    assert!(!game.redo());
 
-    set_row(&mut game, 0, [Some(2), None, Some(2), Some(4)]);
-    assert_eq!(game.move_right(), (4, true));
-    assert_eq!(game.board[0], [None, None, Some(4), Some(4)]);
-
-    set_row(&mut game, 0, [Some(2), Some(4), Some(8), Some(16)]);
-    assert_eq!(game.move_right(), (16, false));
-    assert_eq!(game.board[0], [Some(2), Some(4), Some(8), Some(16)]);
+    // The spawner is restored too, so replaying the move spawns the same tile.
+    assert!(game.undo());
+    let direction = game.legal_moves()[0];
+    assert_eq!(game.move_board(direction).1, spawned);
+    assert_eq!(game.board(), &after);
+    assert!(!game.redo());
 }
 
 #[test]
-fn move_up() {
-    let mut game = Game::new();
+fn undo_limit() {
+    let mut game = Game::with_config(GameConfig {
+        seed: Some(7),
+        undo_limit: Some(2),
+        ..GameConfig::default()
+    });
+    game.reset();
+    for _ in 0..5 {
+        let direction = game.legal_moves()[0];
+        game.move_board(direction);
+    }
+    assert_eq!(game.undos_left(), Some(2));
+    assert!(game.undo());
+    assert!(game.undo());
+    assert!(!game.undo());
+    assert_eq!(game.moves(), 3);
+    assert!(game.result().undo_used);
+}
 
-    set_col(&mut game, 0, [Some(2), Some(2), Some(2), Some(2)]);
-    assert_eq!(game.move_up(), (4, true));
-    assert_eq!(get_col(&mut game, 0), [Some(4), Some(4), None, None]);
+#[test]
+fn keep_going_after_target() {
+    let mut game = Game::with_config(GameConfig {
+        seed: Some(3),
+        target: 8,
+        ..GameConfig::default()
+    });
+    assert_eq!(game.target(), 8);
+    game.board.set(0, 0, Some(4));
+    game.board.set(0, 1, Some(4));
+    assert_eq!(game.move_board(Direction::Left).0, GameStatus::Win);
 
-    set_col(&mut game, 0, [Some(2), None, None, Some(2)]);
-    assert_eq!(game.move_up(), (4, true));
-    assert_eq!(get_col(&mut game, 0), [Some(4), None, None, None]);
+    game.keep_going();
+    assert_eq!(game.status(), GameStatus::Continue);
+    game.board.set(3, 2, Some(8));
+    game.board.set(3, 3, Some(8));
+    assert_eq!(game.move_board(Direction::Right).0, GameStatus::Continue);
+    assert_eq!(game.board().max_tile(), 16);
 
-    set_col(&mut game, 0, [Some(2), Some(2), Some(4), Some(4)]);
-    assert_eq!(game.move_up(), (8, true));
-    assert_eq!(get_col(&mut game, 0), [Some(4), Some(8), None, None]);
+    // A new game aims for the target again.
+    game.reset();
+    assert!(!game.is_endless());
+}
 
-    set_col(&mut game, 0, [Some(2), None, Some(2), Some(4)]);
-    assert_eq!(game.move_up(), (4, true));
-    assert_eq!(get_col(&mut game, 0), [Some(4), Some(4), None, None]);
+#[test]
+fn keep_going_when_stuck() {
+    let mut game = Game::with_config(GameConfig {
+        seed: Some(3),
+        target: 8,
+        spawn: SpawnPolicy {
+            weights: vec![(32, 1.0)],
+            ..SpawnPolicy::default()
+        },
+        ..GameConfig::default()
+    });
+    let rows = [
+        [4, 4, 2, 64],
+        [2, 64, 2, 64],
+        [64, 2, 64, 2],
+        [2, 64, 2, 64],
+    ];
+    for (r, row) in rows.iter().enumerate() {
+        for (c, &val) in row.iter().enumerate() {
+            game.board.set(r, c, Some(val));
+        }
+    }
+    assert_eq!(
+        game.move_board(Direction::Left),
+        (GameStatus::Win, Some((0, 3)))
+    );
 
-    set_col(&mut game, 0, [Some(2), Some(4), Some(8), Some(16)]);
-    assert_eq!(game.move_up(), (16, false));
-    assert_eq!(get_col(&mut game, 0), [Some(2), Some(4), Some(8), Some(16)]);
+    // The winning move filled the board, so there is nothing to keep going with.
+    game.keep_going();
+    assert!(game.legal_moves().is_empty());
+    assert_eq!(game.status(), GameStatus::Lost);
 }
 
 #[test]
-fn move_down() {
-    let mut game = Game::new();
+fn move_events() {
+    let config = GameConfig {
+        seed: Some(11),
+        ..GameConfig::default()
+    };
+    let mut game = Game::with_config(config.clone());
+    let mut twin = Game::with_config(config);
+    game.reset();
+    twin.reset();
+
+    for direction in Direction::ALL.into_iter().cycle().take(30) {
+        let before = game.score();
+        let (status, events) = game.move_events(direction);
+        assert_eq!((status, events.is_empty()), {
+            let (status, coord) = twin.move_board(direction);
+            (status, coord.is_none())
+        });
+        let scored: u32 = events
+            .iter()
+            .map(|event| match event {
+                Event::Scored(points) => *points,
+                _ => 0,
+            })
+            .sum();
+        let merged: u32 = events
+            .iter()
+            .map(|event| match event {
+                Event::Merged { value, .. } => *value,
+                _ => 0,
+            })
+            .sum();
+        assert_eq!(game.score() - before, scored);
+        assert_eq!(scored, merged);
+        if let Some(Event::Spawned { at, value }) = events.last() {
+            assert_eq!(game.board().get(at.0, at.1), Some(*value));
+        }
+        assert_eq!(game.board(), twin.board());
+    }
+}
 
-    set_col(&mut game, 0, [Some(2), Some(2), Some(2), Some(2)]);
-    assert_eq!(game.move_down(), (4, true));
-    assert_eq!(get_col(&mut game, 0), [None, None, Some(4), Some(4)]);
+#[test]
+fn spawn_policy() {
+    let mut game = Game::with_config(GameConfig {
+        seed: Some(3),
+        spawn: SpawnPolicy {
+            weights: vec![(8, 1.0)],
+            per_move: 3,
+            start: 4,
+            ..SpawnPolicy::default()
+        },
+        ..GameConfig::default()
+    });
+    game.reset();
+    assert_eq!(game.board().empty_cells().len(), 12);
+    assert!(game
+        .board()
+        .iter_rows()
+        .flatten()
+        .flatten()
+        .all(|&val| val == 8));
+    assert_eq!(game.spawn_odds(), [(8, 1.0)]);
 
-    set_col(&mut game, 0, [Some(2), None, None, Some(2)]);
-    assert_eq!(game.move_down(), (4, true));
-    assert_eq!(get_col(&mut game, 0), [None, None, None, Some(4)]);
+    let direction = game.legal_moves()[0];
+    let (_, events) = game.move_events(direction);
+    let spawned = events
+        .iter()
+        .filter(|event| matches!(event, Event::Spawned { value: 8, .. }))
+        .count();
+    assert_eq!(spawned, 3);
 
-    set_col(&mut game, 0, [Some(4), Some(4), Some(2), Some(2)]);
-    assert_eq!(game.move_down(), (8, true));
-    assert_eq!(get_col(&mut game, 0), [None, None, Some(8), Some(4)]);
+    let weights = SpawnPolicy::parse_weights("2:9, 4:1").unwrap();
+    assert_eq!(weights, [(2, 9.0), (4, 1.0)]);
+    let policy = SpawnPolicy {
+        weights,
+        ..SpawnPolicy::default()
+    };
+    assert_eq!(policy.odds(), [(2, 0.9), (4, 0.1)]);
+    assert!(SpawnPolicy::parse_weights("2=1").is_err());
+    for weights in [
+        vec![],
+        vec![(3, 1.0)],
+        vec![(2, 0.0)],
+        vec![(2, 1.0), (2, 1.0)],
+    ] {
+        let policy = SpawnPolicy {
+            weights,
+            ..SpawnPolicy::default()
+        };
+        assert!(policy.validate().is_err(), "{policy:?}");
+    }
+}
 
-    set_col(&mut game, 0, [Some(2), None, Some(2), Some(4)]);
-    assert_eq!(game.move_down(), (4, true));
-    assert_eq!(get_col(&mut game, 0), [None, None, Some(4), Some(4)]);
+#[test]
+fn evil_spawner() {
+    let evil = |seed| {
+        let mut game = Game::with_config(GameConfig {
+            seed: Some(seed),
+            spawn: SpawnPolicy {
+                spawner: Spawner::Evil { depth: 1 },
+                ..SpawnPolicy::default()
+            },
+            ..GameConfig::default()
+        });
+        game.reset();
+        game
+    };
+    // The adversary does not use the seed, so every game is the same.
+    let mut games = [evil(1), evil(2)];
+    for direction in [Direction::Left, Direction::Up, Direction::Right] {
+        let [a, b] = games.each_mut().map(|game| game.move_board(direction));
+        assert_eq!(a, b);
+    }
+    assert_eq!(games[0].board(), games[1].board());
 
-    set_col(&mut game, 0, [Some(2), Some(4), Some(8), Some(16)]);
-    assert_eq!(game.move_down(), (16, false));
-    assert_eq!(get_col(&mut game, 0), [Some(2), Some(4), Some(8), Some(16)]);
+    // With one cell left, it spawns the 4 that cannot merge rather than the 2 that can.
+    let game = &mut games[0];
+    let rows = [[2, 4, 2, 4], [4, 2, 4, 2], [2, 4, 2, 4], [8, 0, 16, 8]];
+    for (r, row) in rows.into_iter().enumerate() {
+        for (c, val) in row.into_iter().enumerate() {
+            game.board.set(r, c, (val > 0).then_some(val));
+        }
+    }
+    assert_eq!(
+        game.move_board(Direction::Right),
+        (GameStatus::Lost, Some((3, 0)))
+    );
+    assert_eq!(game.board().get(3, 0), Some(4));
 }
@@ -0,0 +1,74 @@
+use crate::{
+    config::Config,
+    keymap::{self, Action, Keymap},
+};
+use rust_2048::game::Direction;
+use std::path::Path;
+use termion::event::Key;
+
+fn configured(text: &str) -> Result<Keymap, String> {
+    let config = Config::parse(Path::new("config"), text)?;
+    let mut keymap = Keymap::default();
+    keymap.apply(&config)?;
+    Ok(keymap)
+}
+
+#[test]
+fn key_names() {
+    assert_eq!(keymap::parse_key("x"), Some(Key::Char('x')));
+    assert_eq!(keymap::parse_key("Ctrl-s"), Some(Key::Ctrl('s')));
+    assert_eq!(keymap::parse_key("Alt-é"), Some(Key::Alt('é')));
+    assert_eq!(keymap::parse_key("F12"), Some(Key::F(12)));
+    assert_eq!(keymap::parse_key("PageDown"), Some(Key::PageDown));
+    assert_eq!(keymap::parse_key("Space"), Some(Key::Char(' ')));
+    for bad in ["", "F13", "F0", "Ctrl-", "Ctrl-ab", "up", "Shift-x"] {
+        assert_eq!(keymap::parse_key(bad), None, "{bad}");
+    }
+    for name in [
+        "x", "Ctrl-s", "Alt-é", "F12", "PageDown", "Esc", "Enter", "Space",
+    ] {
+        assert_eq!(
+            keymap::parse_key(name).map(keymap::key_name).as_deref(),
+            Some(name)
+        );
+    }
+}
+
+#[test]
+fn rebinding() {
+    let keymap = configured("[keys]\nup = i Up\nundo = k Backspace\n").unwrap();
+    assert_eq!(
+        keymap.action(Key::Char('i')),
+        Some(Action::Move(Direction::Up))
+    );
+    // Rebinding replaces the action's defaults, and may take over another's.
+    assert_eq!(keymap.action(Key::Char('w')), None);
+    assert_eq!(keymap.action(Key::Char('u')), None);
+    assert_eq!(keymap.action(Key::Char('k')), Some(Action::Undo));
+    assert_eq!(keymap.action(Key::Backspace), Some(Action::Undo));
+    assert_eq!(keymap.action(Key::Char('q')), Some(Action::Quit));
+    assert_eq!(keymap.label(Action::Undo).as_deref(), Some("k"));
+    assert_eq!(keymap.label(Action::Quit).as_deref(), Some("q"));
+    assert_eq!(
+        keymap.label(Action::Move(Direction::Up)).as_deref(),
+        Some("i")
+    );
+}
+
+#[test]
+fn errors() {
+    assert_eq!(
+        configured("[keys]\njump = x").err().unwrap(),
+        "config:2: unknown action `jump`"
+    );
+    assert_eq!(
+        configured("[keys]\nup = x Hyper").err().unwrap(),
+        "config:2: unknown key `Hyper`"
+    );
+    assert_eq!(
+        configured("[keys]\nup = x\n\ndown = j x").err().unwrap(),
+        "config:4: key `x` is already bound to `up`"
+    );
+    // Other sections are not ours to check.
+    assert!(configured("[theme]\njump = x").is_ok());
+}
@@ -0,0 +1,90 @@
+use crate::{
+    game::{Direction, Game, GameConfig, SpawnPolicy},
+    protocol::{self, Command},
+    replay::Replay,
+};
+
+fn game(rows: &[[u32; 2]]) -> Game {
+    let mut game = Game::with_config(GameConfig {
+        rows: 2,
+        cols: 2,
+        seed: Some(5),
+        spawn: SpawnPolicy {
+            weights: vec![(2, 1.0)],
+            ..SpawnPolicy::default()
+        },
+        ..GameConfig::default()
+    });
+    for (r, row) in rows.iter().enumerate() {
+        for (c, &val) in row.iter().enumerate() {
+            game.board
+                .set(r, c, if val == 0 { None } else { Some(val) });
+        }
+    }
+    game
+}
+
+fn run(game: &mut Game, input: &str, replay: Option<&mut Replay>) -> String {
+    let mut output = Vec::new();
+    protocol::run(game, input.as_bytes(), &mut output, replay).unwrap();
+    String::from_utf8(output).unwrap()
+}
+
+#[test]
+fn commands() {
+    assert_eq!(Command::parse("left\n"), Ok(Command::Move(Direction::Left)));
+    assert_eq!(Command::parse(" undo"), Ok(Command::Undo));
+    assert_eq!(Command::parse("quit"), Ok(Command::Quit));
+    assert_eq!(
+        Command::parse("sideways"),
+        Err("unknown command `sideways`".to_string())
+    );
+}
+
+#[test]
+fn session() {
+    let mut game = game(&[[2, 2], [8, 4]]);
+    let mut replay = Replay::record(&game);
+    let output = run(
+        &mut game,
+        "up\nleft\n\nright\nfly\nundo\nundo\nquit\nup\n",
+        Some(&mut replay),
+    );
+    assert_eq!(
+        output,
+        "\
+rust_2048 bot 1
+size 2 2
+target 2048
+state 0 0 playing
+2 2
+8 4
+unmoved up
+state 0 0 playing
+2 2
+8 4
+moved left 4 0,1=2
+state 4 1 lost
+4 2
+8 4
+error the game is over
+state 4 1 lost
+4 2
+8 4
+error unknown command `fly`
+state 4 1 lost
+4 2
+8 4
+undone
+state 0 0 playing
+2 2
+8 4
+error nothing to undo
+state 0 0 playing
+2 2
+8 4
+bye
+"
+    );
+    assert_eq!(replay.steps.len(), 2);
+}
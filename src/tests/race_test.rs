@@ -0,0 +1,152 @@
+use crate::{
+    game::{Direction, GameConfig, SpawnPolicy, Spawner},
+    race::{self, Message, Progress, Race, Winner},
+};
+use std::{
+    io::prelude::*,
+    net::TcpListener,
+    thread,
+    time::{Duration, Instant},
+};
+
+fn progress(score: u32, moves: u32, won: bool, stuck: bool) -> Progress {
+    Progress {
+        board: vec![vec![0; 4]; 4],
+        score,
+        moves,
+        won,
+        stuck,
+    }
+}
+
+#[test]
+fn decide() {
+    let playing = progress(100, 10, false, false);
+    let stuck = progress(200, 20, false, true);
+    assert_eq!(race::decide(&playing, &playing), None);
+    // Being stuck first decides nothing: the other side may still pass the score.
+    assert_eq!(race::decide(&stuck, &playing), None);
+    assert_eq!(
+        race::decide(&progress(10, 5, true, false), &stuck),
+        Some(Winner::Host)
+    );
+    assert_eq!(
+        race::decide(&stuck, &progress(10, 5, true, false)),
+        Some(Winner::Guest)
+    );
+    assert_eq!(
+        race::decide(&stuck, &progress(300, 30, false, true)),
+        Some(Winner::Guest)
+    );
+    assert_eq!(race::decide(&stuck, &stuck), Some(Winner::Draw));
+    assert_eq!(
+        race::decide(&progress(0, 50, true, false), &progress(0, 40, true, false)),
+        Some(Winner::Guest)
+    );
+}
+
+/// The next message, waiting up to a few seconds for it.
+fn recv(race: &Race) -> Message {
+    let start = Instant::now();
+    while start.elapsed() < Duration::from_secs(5) {
+        if let Some(message) = race.try_recv() {
+            return message;
+        }
+        thread::sleep(Duration::from_millis(5));
+    }
+    panic!("no message arrived");
+}
+
+#[test]
+fn same_game_on_both_sides() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let config = GameConfig {
+        rows: 3,
+        cols: 5,
+        target: 512,
+        spawn: SpawnPolicy {
+            weights: vec![(2, 3.0), (8, 1.0)],
+            per_move: 2,
+            ..SpawnPolicy::default()
+        },
+        ..GameConfig::default()
+    };
+    let guest = thread::spawn(move || Race::join(addr).unwrap());
+    let mut host = Race::host(&listener, &config).unwrap();
+    let mut guest = guest.join().unwrap();
+
+    assert!(host.host && !guest.host);
+    assert_eq!(guest.config, host.config);
+    assert_eq!(host.config.undo_limit, Some(0));
+    assert_eq!(host.config.spawn, config.spawn);
+
+    // The same moves give the same game.
+    let mut ours = host.game();
+    let mut theirs = guest.game();
+    for direction in [Direction::Left, Direction::Up, Direction::Right] {
+        ours.move_board(direction);
+        theirs.move_board(direction);
+    }
+    assert_eq!(ours.board(), theirs.board());
+
+    guest
+        .send(&Message::Progress(Progress::of(&theirs)))
+        .unwrap();
+    assert_eq!(recv(&host), Message::Progress(Progress::of(&ours)));
+    host.send(&Message::Over {
+        winner: Winner::Draw,
+    })
+    .unwrap();
+    assert_eq!(
+        recv(&guest),
+        Message::Over {
+            winner: Winner::Draw
+        }
+    );
+
+    // Leaving, however it happens, reads as `Quit`.
+    drop(guest);
+    assert_eq!(recv(&host), Message::Quit);
+    assert_eq!(recv(&host), Message::Quit);
+}
+
+#[test]
+fn rejects_unplayable_games() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let host = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let hello = Message::Hello {
+            version: race::VERSION,
+            seed: 1,
+            rows: 4,
+            cols: 4,
+            target: 2048,
+            spawn: vec![(2, 1.0)],
+            per_move: 1,
+            start: 2,
+            evil: Some(9),
+        };
+        writeln!(stream, "{}", serde_json::to_string(&hello).unwrap()).unwrap();
+    });
+    let err = Race::join(addr).err().unwrap();
+    assert_eq!(
+        err.to_string(),
+        "the host's game is not one this build can play"
+    );
+    host.join().unwrap();
+
+    let config = GameConfig {
+        spawn: SpawnPolicy {
+            spawner: Spawner::Evil { depth: 1 },
+            ..SpawnPolicy::default()
+        },
+        ..GameConfig::default()
+    };
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let guest = thread::spawn(move || Race::join(addr).unwrap());
+    let host = Race::host(&listener, &config).unwrap();
+    assert_eq!(guest.join().unwrap().config, host.config);
+}
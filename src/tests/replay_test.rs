@@ -0,0 +1,123 @@
+use crate::{
+    game::{Direction, Game, GameConfig, SpawnPolicy},
+    replay::{Replay, Spawn, Step},
+};
+use std::{env, fs};
+
+fn recorded_game(per_move: u32) -> (Game, Replay) {
+    let mut game = Game::with_config(GameConfig {
+        seed: Some(5),
+        spawn: SpawnPolicy {
+            per_move,
+            ..SpawnPolicy::default()
+        },
+        ..GameConfig::default()
+    });
+    game.reset();
+    let mut replay = Replay::record(&game);
+    for direction in [
+        Direction::Left,
+        Direction::Down,
+        Direction::Right,
+        Direction::Up,
+    ]
+    .into_iter()
+    .cycle()
+    .take(20)
+    {
+        let (_, events) = game.move_events(direction);
+        replay.push_move(direction, &events);
+    }
+    assert!(game.undo());
+    replay.push_undo();
+    assert!(game.undo());
+    replay.push_undo();
+    assert!(game.redo());
+    replay.push_redo();
+    (game, replay)
+}
+
+#[test]
+fn frames_follow_the_game() {
+    let (game, replay) = recorded_game(2);
+    let frames = replay.frames();
+    assert_eq!(frames.len(), replay.steps.len() + 1);
+
+    let last = frames.last().unwrap();
+    assert_eq!(&last.board, game.board());
+    assert_eq!(last.score, game.score());
+    // Undo then redo lands on the frame two steps back.
+    assert_eq!(last, &frames[frames.len() - 3]);
+}
+
+#[test]
+fn round_trip() {
+    let (_, replay) = recorded_game(2);
+    let mut out = Vec::new();
+    replay.save(&mut out).unwrap();
+    assert_eq!(Replay::load(&out[..]).unwrap(), replay);
+
+    // Version 1 files have the same steps with one spawn each.
+    let (_, replay) = recorded_game(1);
+    out.clear();
+    replay.save(&mut out).unwrap();
+    let version_1 =
+        String::from_utf8(out)
+            .unwrap()
+            .replacen("rust_2048 replay 2", "rust_2048 replay 1", 1);
+    assert_eq!(Replay::load(version_1.as_bytes()).unwrap(), replay);
+}
+
+#[test]
+fn rejects_impossible_steps() {
+    let mut replay = Replay::record(&Game::new());
+    replay.start.board.set(0, 3, Some(2));
+    replay.steps.push(Step::Redo);
+
+    let mut out = Vec::new();
+    replay.save(&mut out).unwrap();
+    let err = Replay::load(&out[..]).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "corrupt file at line 6: step cannot be played from this position"
+    );
+
+    // The tile slides to (0, 0), so nothing can spawn there.
+    replay.steps[0] = Step::Move {
+        direction: Direction::Left,
+        spawns: vec![Spawn {
+            row: 0,
+            col: 0,
+            value: 2,
+        }],
+    };
+    out.clear();
+    replay.save(&mut out).unwrap();
+    let err = Replay::load(&out[..]).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "corrupt file at line 6: invalid tile `0,0=2`"
+    );
+}
+
+#[test]
+fn replay_files() {
+    let dir = env::temp_dir().join(format!("rust_2048_replay_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("replay");
+
+    // A new replay replaces the old file whole, leaving nothing else behind.
+    fs::write(&path, "x".repeat(10_000)).unwrap();
+    let (_, replay) = recorded_game(1);
+    replay.save_to_file(&path).unwrap();
+    assert_eq!(Replay::load_from_file(&path).unwrap(), replay);
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+    // One that cannot be written leaves the old file alone.
+    fs::create_dir(dir.join("replay.tmp")).unwrap();
+    assert!(replay.save_to_file(&path).is_err());
+    assert_eq!(Replay::load_from_file(&path).unwrap(), replay);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
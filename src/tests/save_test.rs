@@ -0,0 +1,171 @@
+use crate::{
+    game::{Direction, Game, GameConfig, SpawnPolicy, Spawner},
+    save::SaveError,
+};
+use std::{env, fs};
+
+fn played_game() -> Game {
+    let mut game = Game::with_config(GameConfig {
+        rows: 3,
+        cols: 5,
+        seed: Some(99),
+        undo_limit: Some(4),
+        target: 512,
+        spawn: SpawnPolicy {
+            weights: vec![(2, 3.0), (4, 1.0)],
+            per_move: 1,
+            start: 3,
+            spawner: Spawner::Random,
+        },
+    });
+    game.reset();
+    for direction in [
+        Direction::Left,
+        Direction::Up,
+        Direction::Right,
+        Direction::Down,
+    ]
+    .into_iter()
+    .cycle()
+    .take(12)
+    {
+        game.move_board(direction);
+    }
+    game.undo();
+    game
+}
+
+fn saved(game: &Game) -> String {
+    let mut out = Vec::new();
+    game.save(&mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn round_trip() {
+    let mut game = played_game();
+    let text = saved(&game);
+    let mut loaded = Game::load(text.as_bytes()).unwrap();
+    assert_eq!(saved(&loaded), text);
+
+    assert_eq!(loaded.board(), game.board());
+    assert_eq!(loaded.seed(), 99);
+    assert_eq!(loaded.result(), game.result());
+    assert_eq!(loaded.undos_left(), Some(3));
+    assert_eq!(loaded.target(), 512);
+    assert_eq!(loaded.spawn_policy(), game.spawn_policy());
+
+    // The spawner and both history stacks carry on exactly where they left off.
+    assert_eq!(loaded.redo(), game.redo());
+    for direction in Direction::ALL {
+        assert_eq!(loaded.move_board(direction), game.move_board(direction));
+    }
+    assert!(loaded.undo() && game.undo());
+    assert_eq!(loaded.board(), game.board());
+}
+
+#[test]
+fn rejects_bad_files() {
+    let text = saved(&played_game());
+
+    let err = Game::load("hello\n".as_bytes()).unwrap_err();
+    assert!(matches!(err, SaveError::Corrupt { line: 1, .. }));
+
+    let newer = text.replacen("rust_2048 save 4", "rust_2048 save 5", 1);
+    assert!(matches!(
+        Game::load(newer.as_bytes()),
+        Err(SaveError::Version {
+            found: 5,
+            expected: 4
+        })
+    ));
+
+    let bad_spawn = text.replacen("spawn 2:3 4:1", "spawn 2:3 6:1", 1);
+    let err = Game::load(bad_spawn.as_bytes()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "corrupt file at line 8: spawned tile 6 is not a power of two"
+    );
+
+    let bad_tile = text.replacen(" .", " 3", 1);
+    assert!(matches!(
+        Game::load(bad_tile.as_bytes()),
+        Err(SaveError::Corrupt { .. })
+    ));
+
+    let truncated: String = text.lines().take(10).collect::<Vec<_>>().join("\n");
+    let err = Game::load(truncated.as_bytes()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "corrupt file at line 11: unexpected end of file"
+    );
+}
+
+#[test]
+fn loads_older_versions() {
+    let game = played_game();
+    let version_3 = saved(&game)
+        .replacen("rust_2048 save 4", "rust_2048 save 3", 1)
+        .replacen("spawner random\n", "", 1);
+    let loaded = Game::load(version_3.as_bytes()).unwrap();
+    assert_eq!(loaded.spawn_policy(), game.spawn_policy());
+
+    let version_2 = version_3
+        .replacen("rust_2048 save 3", "rust_2048 save 2", 1)
+        .replacen("spawn 2:3 4:1\nspawn_per_move 1\nstart_tiles 3\n", "", 1);
+    let loaded = Game::load(version_2.as_bytes()).unwrap();
+    assert_eq!(loaded.board(), game.board());
+    assert_eq!(loaded.target(), 512);
+    // Old games keep spawning as they did when they were saved.
+    assert_eq!(loaded.spawn_odds(), [(2, 0.7), (4, 0.3)]);
+
+    let version_1 = version_2
+        .replacen("rust_2048 save 2", "rust_2048 save 1", 1)
+        .replacen("target 512\nkeep_going false\n", "", 1);
+    let loaded = Game::load(version_1.as_bytes()).unwrap();
+    assert_eq!(loaded.board(), game.board());
+    assert_eq!(loaded.target(), 2048);
+    assert!(!loaded.is_endless());
+}
+
+#[test]
+fn evil_spawner_round_trip() {
+    let mut game = Game::with_config(GameConfig {
+        spawn: SpawnPolicy {
+            spawner: Spawner::Evil { depth: 2 },
+            ..SpawnPolicy::default()
+        },
+        ..GameConfig::default()
+    });
+    game.reset();
+    game.move_board(game.legal_moves()[0]);
+    let text = saved(&game);
+    assert!(text.contains("\nspawner evil 2\n"));
+    let mut loaded = Game::load(text.as_bytes()).unwrap();
+    assert_eq!(loaded.spawn_policy(), game.spawn_policy());
+    for direction in Direction::ALL {
+        assert_eq!(loaded.move_board(direction), game.move_board(direction));
+    }
+}
+
+#[test]
+fn save_files() {
+    let dir = env::temp_dir().join(format!("rust_2048_save_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("game");
+
+    // A new save replaces the old one whole, leaving nothing else behind.
+    fs::write(&path, "x".repeat(10_000)).unwrap();
+    let game = played_game();
+    game.save_to_file(&path).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), saved(&game));
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+    // A save that cannot be written leaves the old one alone.
+    assert!(game.save_to_file(dir.join("missing").join("game")).is_err());
+    assert!(game.save_to_file(&dir).is_err());
+    assert_eq!(fs::read_to_string(&path).unwrap(), saved(&game));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,75 @@
+use crate::{
+    game::{Direction, Game, GameConfig},
+    save::SaveError,
+    scores::{Leaderboard, Score},
+};
+use std::{
+    env, fs, thread,
+    time::{Duration, UNIX_EPOCH},
+};
+
+fn score(seed: u64, moves: usize) -> Score {
+    let mut game = Game::with_config(GameConfig {
+        seed: Some(seed),
+        ..GameConfig::default()
+    });
+    game.reset();
+    for direction in Direction::ALL.into_iter().cycle().take(moves) {
+        game.move_board(direction);
+    }
+    let mut score = Score::new(&game, Duration::from_secs(moves as u64));
+    // The file keeps whole seconds.
+    score.date = UNIX_EPOCH + Duration::from_secs(1_760_000_000 + seed);
+    score
+}
+
+#[test]
+fn round_trip_best_first() {
+    let board = Leaderboard {
+        scores: vec![score(1, 10), score(2, 60), score(3, 30)],
+    };
+    let mut out = Vec::new();
+    board.save(&mut out).unwrap();
+    let loaded = Leaderboard::load(out.as_slice()).unwrap();
+
+    assert_eq!(loaded.scores.len(), 3);
+    assert!(loaded
+        .scores
+        .windows(2)
+        .all(|pair| pair[0].result.score >= pair[1].result.score));
+    assert_eq!(loaded.top(1)[0], board.scores[1]);
+    assert_eq!(loaded.top(10).len(), 3);
+    assert_eq!(loaded.scores[0].day(), "2025-10-09");
+
+    let err = Leaderboard::load("rust_2048 scores 1\nentry 1 2 3\n".as_bytes()).unwrap_err();
+    assert!(matches!(err, SaveError::Corrupt { line: 2, .. }));
+}
+
+#[test]
+fn concurrent_writers() {
+    let dir = env::temp_dir().join(format!("rust_2048_scores_{}", std::process::id()));
+    let path = dir.join("scores");
+    let _ = fs::remove_dir_all(&dir);
+    assert_eq!(
+        Leaderboard::load_from_file(&path).unwrap(),
+        Leaderboard::default()
+    );
+
+    let writers: Vec<_> = (0..8)
+        .map(|seed| {
+            let path = path.clone();
+            thread::spawn(move || {
+                for moves in 0..5 {
+                    Leaderboard::record(&path, &score(seed, moves)).unwrap();
+                }
+            })
+        })
+        .collect();
+    for writer in writers {
+        writer.join().unwrap();
+    }
+
+    let board = Leaderboard::load_from_file(&path).unwrap();
+    assert_eq!(board.scores.len(), 40);
+    fs::remove_dir_all(&dir).unwrap();
+}
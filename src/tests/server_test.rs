@@ -0,0 +1,136 @@
+use crate::{
+    game::{Direction, Game, GameConfig},
+    server::{Server, State},
+};
+use serde_json::{json, Value};
+use std::{
+    env, fs,
+    io::{prelude::*, BufReader},
+    net::{TcpListener, TcpStream},
+    os::unix::net::{UnixListener, UnixStream},
+    thread,
+};
+
+/// One line sent and one line read back.
+fn request<S: Read + Write>(reader: &mut BufReader<S>, request: Value) -> Value {
+    writeln!(reader.get_mut(), "{request}").unwrap();
+    let mut line = String::new();
+    reader.read_line(&mut line).unwrap();
+    serde_json::from_str(&line).unwrap()
+}
+
+fn tcp_server() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    thread::spawn(move || Server::new(GameConfig::default()).serve_tcp(listener, |_| {}));
+    addr
+}
+
+#[test]
+fn sessions_match_local_games() {
+    let addr = tcp_server();
+    let moves = [
+        Direction::Left,
+        Direction::Up,
+        Direction::Right,
+        Direction::Down,
+    ];
+    // Several clients at once, each with its own seeded game.
+    let clients: Vec<_> = (0..4)
+        .map(|seed| {
+            let addr = addr.clone();
+            thread::spawn(move || {
+                let mut client = BufReader::new(TcpStream::connect(addr).unwrap());
+                let created = request(&mut client, json!({"cmd": "new", "seed": seed}));
+                assert_eq!(created["ok"], true);
+                let session = created["session"].clone();
+
+                let mut game = Game::with_config(GameConfig {
+                    seed: Some(seed),
+                    ..GameConfig::default()
+                });
+                game.reset();
+                assert_eq!(created["state"], json!(State::of(&game)));
+                for direction in moves.into_iter().cycle().take(40) {
+                    let moved = request(
+                        &mut client,
+                        json!({"cmd": "move", "session": session, "direction": direction.name()}),
+                    );
+                    game.move_board(direction);
+                    assert_eq!(moved["state"], json!(State::of(&game)));
+                }
+                session.as_u64().unwrap()
+            })
+        })
+        .collect();
+    let mut ids: Vec<u64> = clients.into_iter().map(|c| c.join().unwrap()).collect();
+    ids.sort();
+    assert_eq!(ids, [1, 2, 3, 4]);
+
+    // Sessions outlive the connection that made them.
+    let mut client = BufReader::new(TcpStream::connect(&addr).unwrap());
+    assert_eq!(
+        request(&mut client, json!({"cmd": "list"})),
+        json!({"ok": true, "sessions": [1, 2, 3, 4]})
+    );
+    let state = request(&mut client, json!({"cmd": "state", "session": 2}));
+    assert!(state["state"]["moves"].as_u64().unwrap() > 0);
+    let undone = request(&mut client, json!({"cmd": "undo", "session": 2}));
+    assert_eq!(
+        undone["state"]["moves"].as_u64().unwrap() + 1,
+        state["state"]["moves"].as_u64().unwrap()
+    );
+    assert_eq!(
+        request(&mut client, json!({"cmd": "close", "session": 2})),
+        json!({"ok": true, "session": 2})
+    );
+    assert_eq!(
+        request(&mut client, json!({"cmd": "list"}))["sessions"],
+        json!([1, 3, 4])
+    );
+}
+
+#[test]
+fn errors() {
+    let server = Server::new(GameConfig::default());
+    let error = |line: &str| server.respond(line)["error"].as_str().unwrap().to_string();
+    assert!(error("not json").starts_with("bad request"));
+    assert!(error(r#"{"cmd": "fly"}"#).starts_with("bad request"));
+    assert_eq!(error(r#"{"cmd": "state", "session": 9}"#), "no session 9");
+    assert_eq!(
+        error(r#"{"cmd": "new", "rows": 1}"#),
+        "rows and cols must be from 2 to 16"
+    );
+    assert_eq!(
+        error(r#"{"cmd": "new", "target": 100}"#),
+        "target must be a power of two from 4 up"
+    );
+
+    let created = server.respond(r#"{"cmd": "new", "seed": 1, "rows": 2, "cols": 3}"#);
+    assert_eq!(created["state"]["board"].as_array().unwrap().len(), 2);
+    assert_eq!(
+        error(r#"{"cmd": "move", "session": 1, "direction": "north"}"#),
+        "unknown direction `north`"
+    );
+    assert_eq!(error(r#"{"cmd": "undo", "session": 1}"#), "nothing to undo");
+}
+
+#[test]
+fn unix_socket() {
+    let dir = env::temp_dir().join(format!("rust_2048_server_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("socket");
+    let _ = fs::remove_file(&path);
+    let listener = UnixListener::bind(&path).unwrap();
+    thread::spawn(move || Server::new(GameConfig::default()).serve_unix(listener, |_| {}));
+
+    let mut client = BufReader::new(UnixStream::connect(&path).unwrap());
+    let created = request(&mut client, json!({"cmd": "new", "seed": 3}));
+    assert_eq!(created["session"], 1);
+    let moved = request(
+        &mut client,
+        json!({"cmd": "move", "session": 1, "direction": "up"}),
+    );
+    assert_eq!(moved["ok"], true);
+    fs::remove_dir_all(&dir).unwrap();
+}
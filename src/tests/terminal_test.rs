@@ -0,0 +1,42 @@
+use crate::terminal::{Capabilities, ColorMode};
+
+fn detect(vars: &[(&str, &str)]) -> Capabilities {
+    Capabilities::detect_with(|name| {
+        vars.iter()
+            .find(|(var, _)| *var == name)
+            .map_or(String::new(), |(_, value)| value.to_string())
+    })
+}
+
+#[test]
+fn colours() {
+    let color = |vars| detect(vars).color;
+    assert_eq!(color(&[]), ColorMode::Ansi16);
+    assert_eq!(color(&[("TERM", "xterm")]), ColorMode::Ansi16);
+    assert_eq!(color(&[("TERM", "xterm-256color")]), ColorMode::Ansi256);
+    assert_eq!(
+        color(&[("TERM", "xterm-256color"), ("COLORTERM", "truecolor")]),
+        ColorMode::TrueColor
+    );
+    assert_eq!(color(&[("COLORTERM", "24bit")]), ColorMode::TrueColor);
+    assert_eq!(
+        color(&[("COLORTERM", "truecolor"), ("NO_COLOR", "1")]),
+        ColorMode::Plain
+    );
+    assert_eq!(
+        color(&[("COLORTERM", "truecolor"), ("TERM", "dumb")]),
+        ColorMode::Plain
+    );
+}
+
+#[test]
+fn character_set() {
+    let unicode = |vars| detect(vars).unicode;
+    assert!(unicode(&[]));
+    assert!(unicode(&[("LANG", "en_GB.UTF-8")]));
+    assert!(unicode(&[("LC_CTYPE", "C.utf8"), ("LANG", "C")]));
+    assert!(!unicode(&[("LANG", "C")]));
+    // The first one set wins, even over a UTF-8 one after it.
+    assert!(!unicode(&[("LC_ALL", "POSIX"), ("LANG", "en_US.UTF-8")]));
+    assert!(!unicode(&[("TERM", "dumb"), ("LANG", "en_US.UTF-8")]));
+}
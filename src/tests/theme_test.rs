@@ -0,0 +1,122 @@
+use crate::{
+    config::Config,
+    terminal::ColorMode,
+    theme::{Colour, Style, Theme},
+};
+use std::path::Path;
+
+fn load(name: &str, text: &str) -> Result<Theme, String> {
+    Theme::load(name, &Config::parse(Path::new("config"), text)?)
+}
+
+#[test]
+fn colour_modes() {
+    let rgb = |r, g, b, mode| Colour::Rgb(r, g, b).for_mode(mode);
+    // Entries of the xterm 256-colour palette: the cube, then the greys.
+    for ((r, g, b), index) in [
+        ((0, 0, 0), 16),
+        ((255, 0, 0), 196),
+        ((95, 135, 175), 67),
+        ((255, 255, 255), 231),
+        ((128, 128, 128), 244),
+        ((8, 8, 8), 232),
+        ((238, 238, 238), 255),
+        ((250, 128, 114), 209),
+    ] {
+        assert_eq!(
+            rgb(r, g, b, ColorMode::Ansi256),
+            Some(Colour::Indexed(index)),
+            "{r} {g} {b}"
+        );
+    }
+    for ((r, g, b), index) in [
+        ((10, 10, 10), 0),
+        ((200, 10, 0), 1),
+        ((255, 0, 0), 9),
+        ((128, 128, 128), 8),
+        ((90, 90, 250), 12),
+        ((250, 250, 250), 15),
+    ] {
+        assert_eq!(
+            rgb(r, g, b, ColorMode::Ansi16),
+            Some(Colour::Basic(index)),
+            "{r} {g} {b}"
+        );
+    }
+    assert_eq!(
+        rgb(1, 2, 3, ColorMode::TrueColor),
+        Some(Colour::Rgb(1, 2, 3))
+    );
+    assert_eq!(rgb(1, 2, 3, ColorMode::Plain), None);
+}
+
+#[test]
+fn styles() {
+    assert_eq!(
+        Style::parse("bold #ff8000 on #00000a"),
+        Some(Style {
+            fg: Some(Colour::Rgb(255, 128, 0)),
+            bg: Some(Colour::Rgb(0, 0, 10)),
+            bold: true,
+        })
+    );
+    assert_eq!(
+        Style::parse("on #ffffff"),
+        Some(Style {
+            bg: Some(Colour::Rgb(255, 255, 255)),
+            ..Style::default()
+        })
+    );
+    assert_eq!(Style::parse(""), Some(Style::default()));
+    for bad in ["#fff", "#gg0000", "red", "on", "bold on italic"] {
+        assert_eq!(Style::parse(bad), None, "{bad}");
+    }
+}
+
+#[test]
+fn custom_themes() {
+    let text = "[theme.ocean]\nbase = classic\n1 = bold\n8 = #e0f2fe on #0c4a6e\n\
+                empty = on #082f49\n";
+    let ocean = load("ocean", text).unwrap();
+    let classic = Theme::built_in("classic").unwrap();
+    assert_eq!(ocean.tile(1), Style::default().bold());
+    assert_eq!(ocean.tile(4), classic.tile(4));
+    assert_eq!(ocean.tile(8), Style::on(0xe0f2fe, 0x0c4a6e));
+    // A tile takes the style of the largest value not above its own.
+    assert_eq!(ocean.tile(12), ocean.tile(8));
+    assert_eq!(ocean.tile(1 << 20), classic.tile(4096));
+    assert_eq!(ocean.empty.bg, Some(Colour::hex(0x082f49)));
+    assert_eq!(ocean.grid, classic.grid);
+
+    // Without a base, a theme starts from the default one.
+    let plain = load("plain", "[theme.plain]\nscore = #000000").unwrap();
+    assert_eq!(plain.tile(64), Theme::default().tile(64));
+    assert_eq!(plain.score, Style::fg(0));
+    // Tiles below the smallest styled value take its style.
+    assert_eq!(plain.tile(1), Theme::default().tile(2));
+    assert_eq!(load("mono", "").unwrap(), Theme::built_in("mono").unwrap());
+
+    // The base applies first, wherever it is listed.
+    let late = load("late", "[theme.late]\n1 = bold\nbase = classic").unwrap();
+    assert_eq!(late.tile(1), Style::default().bold());
+    assert_eq!(late.tile(4), classic.tile(4));
+}
+
+#[test]
+fn errors() {
+    assert_eq!(
+        load("nope", "").err().unwrap(),
+        "unknown theme `nope`; the built-in themes are rose, classic, high-contrast, mono"
+    );
+    for (line, message) in [
+        ("base = nope", "unknown theme `nope`"),
+        ("2 = pink", "invalid style `pink`"),
+        ("3 = bold", "unknown setting `3`"),
+        ("cursor = bold", "unknown setting `cursor`"),
+    ] {
+        assert_eq!(
+            load("x", &format!("[theme.x]\n{line}")).err().unwrap(),
+            format!("config:2: {message}")
+        );
+    }
+}
@@ -0,0 +1,26 @@
+use crate::tui::Layout;
+use rust_2048::board::Board;
+
+#[test]
+fn layout_fits_the_terminal() {
+    let board = Board::new(4, 4);
+    let layout = |width, height, x, y| Layout {
+        width,
+        height,
+        x,
+        y,
+    };
+    // The biggest cells that fit, centred.
+    assert_eq!(Layout::fit(&board, (200, 60)), Ok(layout(14, 7, 70, 12)));
+    assert_eq!(Layout::fit(&board, (80, 24)), Ok(layout(6, 3, 26, 2)));
+    // Then one line per row, with nothing between them.
+    assert_eq!(Layout::fit(&board, (40, 10)), Ok(layout(6, 1, 6, 1)));
+    // And failing that, the size the compact layout would need.
+    assert_eq!(Layout::fit(&board, (20, 8)), Err((29, 10)));
+    assert_eq!(Layout::fit(&board, (28, 40)), Err((29, 10)));
+
+    // Cells widen to hold the biggest tile.
+    let mut board = Board::new(16, 16);
+    board.set(3, 3, Some(131072));
+    assert_eq!(Layout::fit(&board, (200, 60)), Ok(layout(8, 1, 28, 20)));
+}
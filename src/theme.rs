@@ -0,0 +1,382 @@
+//! Colours for the board, chosen by name.
+//!
+//! The built-in themes are `rose` (the default), `classic`, `high-contrast` and `mono`.
+//! The config file picks one and can define more, each starting from a built-in one:
+//!
+//! ```text
+//! [theme]
+//! name = ocean
+//!
+//! [theme.ocean]
+//! base = classic
+//! 2 = #e0f2fe on #0c4a6e
+//! 2048 = bold #ffffff on #0369a1
+//! empty = on #082f49
+//! grid = #0369a1
+//! score = bold #38bdf8
+//! ```
+//!
+//! A style is any of `bold`, a foreground colour and `on` followed by a background
+//! colour, with colours written `#rrggbb`. A tile takes the style listed for the largest
+//! value not above its own.
+
+use crate::{config::Config, terminal::ColorMode};
+use termion::{color, style};
+
+pub const DEFAULT: &str = "rose";
+
+/// The usual RGB values of the 16 basic colours, as in xterm.
+const BASIC: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Colour {
+    /// A 24-bit colour.
+    Rgb(u8, u8, u8),
+    /// An entry of the 256-colour palette.
+    Indexed(u8),
+    /// One of the 16 basic colours.
+    Basic(u8),
+}
+
+impl Colour {
+    fn parse(text: &str) -> Option<Colour> {
+        let hex = text.strip_prefix('#').filter(|hex| hex.len() == 6)?;
+        let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+        Some(Colour::Rgb(channel(0)?, channel(2)?, channel(4)?))
+    }
+
+    pub(crate) fn hex(hex: u32) -> Colour {
+        Colour::Rgb((hex >> 16) as u8, (hex >> 8) as u8, hex as u8)
+    }
+
+    /// The nearest colour `mode` can show, or `None` if it shows no colour.
+    pub(crate) fn for_mode(self, mode: ColorMode) -> Option<Colour> {
+        let Colour::Rgb(r, g, b) = self else {
+            return Some(self);
+        };
+        let distance = |(r2, g2, b2): (u8, u8, u8)| {
+            [(r, r2), (g, g2), (b, b2)]
+                .into_iter()
+                .map(|(a, b)| (i32::from(a) - i32::from(b)).pow(2))
+                .sum::<i32>()
+        };
+        match mode {
+            ColorMode::TrueColor => Some(self),
+            ColorMode::Ansi256 => {
+                // The 6x6x6 colour cube, whose levels are 0 and then 95 to 255 in steps
+                // of 40, against the 24 greys from 8 to 238.
+                let level = |v: u8| if v < 48 { 0 } else { (v.max(55) - 35) / 40 };
+                let value = |level: u8| if level == 0 { 0 } else { 55 + 40 * level };
+                let (lr, lg, lb) = (level(r), level(g), level(b));
+                let cube = (value(lr), value(lg), value(lb));
+                let grey = ((u16::from(r) + u16::from(g) + u16::from(b)) / 3) as u8;
+                let step = (grey.saturating_sub(3) / 10).min(23);
+                let grey_value = 8 + 10 * step;
+                if distance((grey_value, grey_value, grey_value)) < distance(cube) {
+                    Some(Colour::Indexed(232 + step))
+                } else {
+                    Some(Colour::Indexed(16 + 36 * lr + 6 * lg + lb))
+                }
+            }
+            ColorMode::Ansi16 => (0..16)
+                .min_by_key(|&i| distance(BASIC[i as usize]))
+                .map(Colour::Basic),
+            ColorMode::Plain => None,
+        }
+    }
+
+    fn fg(self) -> String {
+        match self {
+            Colour::Rgb(r, g, b) => color::Fg(color::Rgb(r, g, b)).to_string(),
+            Colour::Indexed(n) => color::Fg(color::AnsiValue(n)).to_string(),
+            Colour::Basic(n) if n < 8 => format!("\x1b[{}m", 30 + n),
+            Colour::Basic(n) => format!("\x1b[{}m", 90 + n - 8),
+        }
+    }
+
+    fn bg(self) -> String {
+        match self {
+            Colour::Rgb(r, g, b) => color::Bg(color::Rgb(r, g, b)).to_string(),
+            Colour::Indexed(n) => color::Bg(color::AnsiValue(n)).to_string(),
+            Colour::Basic(n) if n < 8 => format!("\x1b[{}m", 40 + n),
+            Colour::Basic(n) => format!("\x1b[{}m", 100 + n - 8),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Style {
+    pub fg: Option<Colour>,
+    pub bg: Option<Colour>,
+    pub bold: bool,
+}
+
+impl Style {
+    pub(crate) fn fg(hex: u32) -> Style {
+        Style {
+            fg: Some(Colour::hex(hex)),
+            ..Style::default()
+        }
+    }
+
+    pub(crate) fn on(fg: u32, bg: u32) -> Style {
+        Style {
+            fg: Some(Colour::hex(fg)),
+            bg: Some(Colour::hex(bg)),
+            bold: false,
+        }
+    }
+
+    pub(crate) fn bold(self) -> Style {
+        Style { bold: true, ..self }
+    }
+
+    /// Parse `[bold] [#fg] [on #bg]`.
+    pub fn parse(text: &str) -> Option<Style> {
+        let mut style = Style::default();
+        let mut words = text.split_whitespace();
+        while let Some(word) = words.next() {
+            match word {
+                "bold" => style.bold = true,
+                "on" => style.bg = Some(Colour::parse(words.next()?)?),
+                colour => style.fg = Some(Colour::parse(colour)?),
+            }
+        }
+        Some(style)
+    }
+
+    /// The escape codes that switch to this style.
+    pub fn codes(&self) -> String {
+        let mut codes = String::new();
+        if self.bold {
+            codes.push_str(style::Bold.as_ref());
+        }
+        if let Some(fg) = self.fg {
+            codes.push_str(&fg.fg());
+        }
+        if let Some(bg) = self.bg {
+            codes.push_str(&bg.bg());
+        }
+        codes
+    }
+
+    fn for_mode(self, mode: ColorMode) -> Style {
+        Style {
+            fg: self.fg.and_then(|fg| fg.for_mode(mode)),
+            bg: self.bg.and_then(|bg| bg.for_mode(mode)),
+            bold: self.bold,
+        }
+    }
+
+    /// `text` drawn in this style.
+    pub fn paint(&self, text: &str) -> String {
+        let codes = self.codes();
+        if codes.is_empty() {
+            text.to_string()
+        } else {
+            format!("{codes}{text}{}", style::Reset)
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Theme {
+    /// Tile styles by the smallest value they apply to, in increasing order.
+    tiles: Vec<(u32, Style)>,
+    /// Empty cells.
+    pub empty: Style,
+    /// Lines between cells.
+    pub grid: Style,
+    /// The score below the board.
+    pub score: Style,
+    /// Draw lines with plain ASCII instead of box-drawing characters.
+    pub ascii: bool,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::built_in(DEFAULT).unwrap()
+    }
+}
+
+impl Theme {
+    pub const BUILT_IN: [&'static str; 4] = ["rose", "classic", "high-contrast", "mono"];
+
+    pub fn built_in(name: &str) -> Option<Theme> {
+        let theme = match name {
+            "rose" => Theme {
+                tiles: vec![
+                    (2, Style::fg(0xfff1f2)),
+                    (8, Style::fg(0xffe4e6)),
+                    (16, Style::fg(0xfecdd3)),
+                    (32, Style::fg(0xfda4af)),
+                    (64, Style::fg(0xfb7185)),
+                    (128, Style::fg(0xf43f5e)),
+                    (256, Style::fg(0xe11d48)),
+                    (512, Style::fg(0xbe123c)),
+                    (1024, Style::fg(0x9f1239)),
+                    (2048, Style::fg(0x881337)),
+                    (4096, Style::fg(0x4c0519)),
+                ],
+                empty: Style::default(),
+                grid: Style::default(),
+                score: Style::default().bold(),
+                ascii: false,
+            },
+            // The colours of the original web game.
+            "classic" => Theme {
+                tiles: vec![
+                    (2, Style::on(0x776e65, 0xeee4da)),
+                    (4, Style::on(0x776e65, 0xede0c8)),
+                    (8, Style::on(0xf9f6f2, 0xf2b179)),
+                    (16, Style::on(0xf9f6f2, 0xf59563)),
+                    (32, Style::on(0xf9f6f2, 0xf67c5f)),
+                    (64, Style::on(0xf9f6f2, 0xf65e3b)),
+                    (128, Style::on(0xf9f6f2, 0xedcf72).bold()),
+                    (256, Style::on(0xf9f6f2, 0xedcc61).bold()),
+                    (512, Style::on(0xf9f6f2, 0xedc850).bold()),
+                    (1024, Style::on(0xf9f6f2, 0xedc53f).bold()),
+                    (2048, Style::on(0xf9f6f2, 0xedc22e).bold()),
+                    (4096, Style::on(0xf9f6f2, 0x3c3a32).bold()),
+                ],
+                empty: Style {
+                    bg: Some(Colour::hex(0xcdc1b4)),
+                    ..Style::default()
+                },
+                grid: Style::fg(0xbbada0),
+                score: Style::fg(0xedc22e).bold(),
+                ascii: false,
+            },
+            "high-contrast" => Theme {
+                tiles: vec![
+                    (2, Style::on(0xffffff, 0x000000)),
+                    (4, Style::on(0xffff00, 0x000000)),
+                    (8, Style::on(0x00ffff, 0x000000)),
+                    (16, Style::on(0x00ff00, 0x000000)),
+                    (32, Style::on(0xff00ff, 0x000000)),
+                    (64, Style::on(0xff8000, 0x000000)),
+                    (128, Style::on(0x000000, 0xffffff).bold()),
+                    (256, Style::on(0x000000, 0xffff00).bold()),
+                    (512, Style::on(0x000000, 0x00ffff).bold()),
+                    (1024, Style::on(0x000000, 0x00ff00).bold()),
+                    (2048, Style::on(0x000000, 0xff00ff).bold()),
+                    (4096, Style::on(0x000000, 0xff8000).bold()),
+                ],
+                empty: Style::default(),
+                grid: Style::fg(0xffffff),
+                score: Style::fg(0xffff00).bold(),
+                ascii: false,
+            },
+            "mono" => Theme {
+                tiles: vec![(2, Style::default()), (128, Style::default().bold())],
+                empty: Style::default(),
+                grid: Style::default(),
+                score: Style::default().bold(),
+                ascii: false,
+            },
+            _ => return None,
+        };
+        Some(theme)
+    }
+
+    /// The theme called `name`: a `[theme.name]` section of `config` if there is one,
+    /// otherwise a built-in theme.
+    pub fn load(name: &str, config: &Config) -> Result<Theme, String> {
+        let section = format!("theme.{name}");
+        let mut settings = config.section(&section).peekable();
+        if settings.peek().is_none() {
+            return Theme::built_in(name).ok_or_else(|| {
+                format!(
+                    "unknown theme `{name}`; the built-in themes are {}",
+                    Theme::BUILT_IN.join(", ")
+                )
+            });
+        }
+
+        // The base comes first wherever it is listed, so that it never overrides the rest.
+        let (base, settings): (Vec<_>, Vec<_>) =
+            settings.partition(|setting| setting.name == "base");
+        let mut theme = Theme::default();
+        for setting in base {
+            let value = &setting.value;
+            theme = Theme::built_in(value)
+                .ok_or_else(|| config.error(setting, &format!("unknown theme `{value}`")))?;
+        }
+        for setting in settings {
+            let value = &setting.value;
+            let style = Style::parse(value)
+                .ok_or_else(|| config.error(setting, &format!("invalid style `{value}`")))?;
+            match setting.name.as_str() {
+                "empty" => theme.empty = style,
+                "grid" => theme.grid = style,
+                "score" => theme.score = style,
+                tile => match tile.parse::<u32>() {
+                    Ok(val) if val.is_power_of_two() => theme.set_tile(val, style),
+                    _ => return Err(config.error(setting, &format!("unknown setting `{tile}`"))),
+                },
+            }
+        }
+        Ok(theme)
+    }
+
+    /// The theme as shown by a terminal with `mode` colours.
+    pub fn for_terminal(mut self, mode: ColorMode, ascii: bool) -> Theme {
+        for (_, style) in &mut self.tiles {
+            *style = style.for_mode(mode);
+        }
+        for style in [&mut self.empty, &mut self.grid, &mut self.score] {
+            *style = style.for_mode(mode);
+        }
+        self.ascii = ascii;
+        self
+    }
+
+    /// Replace box-drawing characters in `text` if the theme is drawn in ASCII.
+    pub fn lines(&self, text: &str) -> String {
+        if !self.ascii {
+            return text.to_string();
+        }
+        text.chars()
+            .map(|c| match c {
+                '─' | '═' => '-',
+                '│' | '║' | '┆' => '|',
+                '┌' | '┬' | '┐' | '├' | '┼' | '┤' | '└' | '┴' | '┘' | '╔' | '╗' | '╚' | '╝'
+                | '╧' => '+',
+                c => c,
+            })
+            .collect()
+    }
+
+    /// The style of tiles of `val`.
+    pub fn tile(&self, val: u32) -> Style {
+        self.tiles
+            .iter()
+            .rev()
+            .find(|&&(from, _)| from <= val)
+            .or(self.tiles.first())
+            .map_or(Style::default(), |&(_, style)| style)
+    }
+
+    fn set_tile(&mut self, val: u32, style: Style) {
+        self.tiles.retain(|&(from, _)| from != val);
+        self.tiles.push((val, style));
+        self.tiles.sort_by_key(|&(from, _)| from);
+    }
+}
@@ -0,0 +1,1301 @@
+use crate::{
+    keymap::{key_name, Action, Keymap},
+    terminal,
+    theme::Theme,
+};
+use rust_2048::{
+    ai::PlayerKind,
+    board::{Board, MAX_SIZE, MIN_SIZE},
+    broadcast::{Broadcaster, Spectator},
+    game::{Event, Game, GameResult, GameStatus},
+    race::{self, Message, Progress, Race, Winner},
+    replay::Replay,
+    scores::{Leaderboard, Score},
+};
+use std::{
+    collections::VecDeque,
+    io::{self, prelude::*},
+    path::PathBuf,
+    thread,
+    time::{Duration, Instant},
+};
+use termion::{
+    clear, cursor,
+    event::Key::{self, Char},
+    input::{Keys, TermRead},
+    raw::IntoRawMode,
+    style, AsyncReader,
+};
+
+/// Where `s` saves the game when no save file was given.
+const DEFAULT_SAVE_PATH: &str = "rust_2048.save";
+/// Frames drawn while tiles slide; the merge pop and spawn fade-in take one more each.
+const SLIDE_FRAMES: u32 = 3;
+
+/// Front end settings that do not affect the rules.
+pub struct Options {
+    /// Save file written by `s` and when quitting mid-game.
+    pub save_path: Option<PathBuf>,
+    /// Replay file written when the game ends or the player quits.
+    pub record_path: Option<PathBuf>,
+    /// Start with the AI playing; `a` toggles it either way.
+    pub autoplay: bool,
+    /// The AI used for autoplay and hints.
+    pub ai: PlayerKind,
+    /// Search depth of the expectimax AI.
+    pub ai_depth: u32,
+    /// Pause between AI moves.
+    pub autoplay_delay: Duration,
+    /// High-score table that finished games are added to; `None` keeps no scores.
+    pub scores_path: Option<PathBuf>,
+    /// Length of the slide, merge and spawn animation after each move; zero turns it off.
+    pub animation: Duration,
+    pub keymap: Keymap,
+    pub theme: Theme,
+    /// Where the game is shown to spectators, if anywhere.
+    pub broadcast: Option<Broadcaster>,
+}
+
+impl Options {
+    /// Show spectators the game, if it is being broadcast. Call this whenever the game
+    /// changes, and not otherwise, since each call serialises the whole game.
+    pub fn publish(&self, game: &Game) {
+        if let Some(broadcast) = &self.broadcast {
+            broadcast.publish(game);
+        }
+    }
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            save_path: None,
+            record_path: None,
+            autoplay: false,
+            ai: PlayerKind::Expectimax,
+            ai_depth: 3,
+            autoplay_delay: Duration::from_millis(100),
+            scores_path: None,
+            animation: Duration::from_millis(100),
+            keymap: Keymap::default(),
+            theme: Theme::default(),
+            broadcast: None,
+        }
+    }
+}
+
+/// Run the interactive terminal front end until the player quits, wins or loses.
+///
+/// The game is played from its current state, so call [`Game::reset`] first for a new one.
+pub fn start(game: &mut Game, options: &Options) -> io::Result<GameResult> {
+    let stdout = io::stdout().lock();
+    let mut stdout = stdout.into_raw_mode()?;
+    let mut keys = termion::async_stdin().keys();
+    terminal::watch_resize();
+    // Keys pressed during an animation, handled before reading any more.
+    let mut pending = VecDeque::new();
+
+    let mut replay = options.record_path.as_ref().map(|_| Replay::record(game));
+    let mut ai = options.ai.player(options.ai_depth, game.seed());
+    let mut autoplay = options.autoplay;
+    let mut last_move = Instant::now();
+    let mut started = Instant::now();
+    // Reported once the terminal is restored, since the screen is about to be cleared.
+    let mut warning = None;
+
+    write!(stdout, "{}", clear::All)?;
+    draw(&mut stdout, &options.theme, game, &[])?;
+    options.publish(game);
+
+    loop {
+        if terminal::resized() {
+            write!(stdout, "{}", clear::All)?;
+            draw(&mut stdout, &options.theme, game, &[])?;
+        }
+        let key = match pending.pop_front() {
+            Some(key) => Some(key),
+            None => keys.next().transpose()?,
+        };
+        let direction = match key.map(|key| options.keymap.action(key)) {
+            Some(Some(Action::Move(direction))) => direction,
+            Some(Some(Action::Undo)) => {
+                if game.undo() {
+                    if let Some(replay) = &mut replay {
+                        replay.push_undo();
+                    }
+                    draw(&mut stdout, &options.theme, game, &[])?;
+                    options.publish(game);
+                }
+                continue;
+            }
+            Some(Some(Action::Redo)) => {
+                if game.redo() {
+                    if let Some(replay) = &mut replay {
+                        replay.push_redo();
+                    }
+                    draw(&mut stdout, &options.theme, game, &[])?;
+                    options.publish(game);
+                }
+                continue;
+            }
+            Some(Some(Action::Restart)) => {
+                autoplay = false;
+                let recorded = restart(game, options, &mut replay, &mut started)?;
+                write!(stdout, "{}", clear::All)?;
+                draw(&mut stdout, &options.theme, game, &[])?;
+                options.publish(game);
+                if let Err(text) = recorded {
+                    note(&mut stdout, game.board(), &text)?;
+                }
+                continue;
+            }
+            Some(Some(Action::Save)) => {
+                let path = options
+                    .save_path
+                    .clone()
+                    .unwrap_or_else(|| PathBuf::from(DEFAULT_SAVE_PATH));
+                let text = match game.save_to_file(&path) {
+                    Ok(()) => format!("Saved to {}", path.display()),
+                    Err(err) => format!("Could not save to {}: {err}", path.display()),
+                };
+                note(&mut stdout, game.board(), &text)?;
+                continue;
+            }
+            Some(Some(Action::Autoplay)) => {
+                autoplay = !autoplay;
+                last_move = Instant::now();
+                let text = if autoplay {
+                    "Autoplay on"
+                } else {
+                    "Autoplay off"
+                };
+                note(&mut stdout, game.board(), text)?;
+                continue;
+            }
+            Some(Some(Action::Hint)) => {
+                let text = match ai.choose(game) {
+                    Some(direction) => format!("Hint: {}", direction.name()),
+                    None => "Hint: no moves left".to_string(),
+                };
+                note(&mut stdout, game.board(), &text)?;
+                continue;
+            }
+            Some(Some(Action::Scores)) => {
+                show_leaderboard(&mut stdout, &mut keys, options)?;
+                write!(stdout, "{}", clear::All)?;
+                draw(&mut stdout, &options.theme, game, &[])?;
+                continue;
+            }
+            Some(Some(Action::Quit)) => {
+                match &options.save_path {
+                    // The game will be resumed, so it is not over yet.
+                    Some(path) if game.status() == GameStatus::Continue => {
+                        warning = game
+                            .save_to_file(path)
+                            .err()
+                            .map(|err| format!("Could not save to {}: {err}", path.display()));
+                    }
+                    _ if game.moves() > 0 => {
+                        warning = record_score(options, game, started).err();
+                    }
+                    _ => {}
+                }
+                break;
+            }
+            // An unbound key.
+            Some(None) => continue,
+            None if autoplay && last_move.elapsed() >= options.autoplay_delay => {
+                match ai.choose(game) {
+                    Some(direction) => direction,
+                    None => {
+                        autoplay = false;
+                        continue;
+                    }
+                }
+            }
+            None => {
+                thread::sleep(Duration::from_millis(10));
+                continue;
+            }
+        };
+        last_move = Instant::now();
+        let before = game.board().clone();
+        let (status, events) = game.move_events(direction);
+        let spawned: Vec<_> = events
+            .iter()
+            .filter_map(|event| match *event {
+                Event::Spawned { at, .. } => Some(at),
+                _ => None,
+            })
+            .collect();
+        if let Some(replay) = &mut replay {
+            replay.push_move(direction, &events);
+        }
+        if !events.is_empty() {
+            options.publish(game);
+            if !options.animation.is_zero() {
+                let mut animation = Animation {
+                    theme: &options.theme,
+                    keys: &mut keys,
+                    pending: &mut pending,
+                    frame: options.animation / (SLIDE_FRAMES + 2),
+                };
+                animation.play(&mut stdout, &before, game.board(), &events)?;
+            }
+            draw(&mut stdout, &options.theme, game, &spawned)?;
+        }
+        if status == GameStatus::Continue {
+            continue;
+        }
+
+        autoplay = false;
+        // Keys typed before the game ended are not meant for the end screen.
+        pending.clear();
+        let choice = loop {
+            let choice = end_screen(
+                &mut stdout,
+                &mut keys,
+                &options.keymap,
+                &options.theme,
+                game,
+            )?;
+            if let EndChoice::Continue = choice {
+                game.keep_going();
+                // The winning move may also have been the last one.
+                if game.status() == GameStatus::Lost {
+                    write!(stdout, "{}", clear::All)?;
+                    draw(&mut stdout, &options.theme, game, &[])?;
+                    options.publish(game);
+                    continue;
+                }
+            }
+            break choice;
+        };
+        match choice {
+            EndChoice::Continue => {}
+            EndChoice::Undo => {
+                game.undo();
+                if let Some(replay) = &mut replay {
+                    replay.push_undo();
+                }
+            }
+            EndChoice::Restart => {
+                let recorded = restart(game, options, &mut replay, &mut started)?;
+                write!(stdout, "{}", clear::All)?;
+                draw(&mut stdout, &options.theme, game, &[])?;
+                options.publish(game);
+                if let Err(text) = recorded {
+                    note(&mut stdout, game.board(), &text)?;
+                }
+                continue;
+            }
+            EndChoice::Quit => {
+                warning = record_score(options, game, started).err();
+                break;
+            }
+        }
+        write!(stdout, "{}", clear::All)?;
+        draw(&mut stdout, &options.theme, game, &[])?;
+        options.publish(game);
+    }
+
+    if let (Some(path), Some(replay)) = (&options.record_path, &replay) {
+        replay.save_to_file(path)?;
+    }
+
+    // Leave the terminal as we found it.
+    write!(
+        stdout,
+        "{}{}{}",
+        clear::All,
+        style::Reset,
+        cursor::Goto(1, 1)
+    )?;
+    stdout.flush()?;
+    drop(stdout);
+    if let Some(warning) = warning {
+        eprintln!("{warning}");
+    }
+    Ok(game.result())
+}
+
+/// Finish the current game, recording it if any move was made, and start a new one
+/// with a random seed.
+fn restart(
+    game: &mut Game,
+    options: &Options,
+    replay: &mut Option<Replay>,
+    started: &mut Instant,
+) -> io::Result<Result<(), String>> {
+    if let (Some(path), Some(replay)) = (&options.record_path, &replay) {
+        replay.save_to_file(path)?;
+    }
+    let recorded = if game.moves() > 0 {
+        record_score(options, game, *started)
+    } else {
+        Ok(())
+    };
+    game.reseed(rand::random());
+    *started = Instant::now();
+    if let Some(replay) = replay {
+        *replay = Replay::record(game);
+    }
+    Ok(recorded)
+}
+
+/// Add the game to the high-score table, if one is kept.
+fn record_score(options: &Options, game: &Game, started: Instant) -> Result<(), String> {
+    let Some(path) = &options.scores_path else {
+        return Ok(());
+    };
+    Leaderboard::record(path, &Score::new(game, started.elapsed()))
+        .map_err(|err| format!("Could not record score in {}: {err}", path.display()))
+}
+
+/// Show the best scores until a key is pressed.
+fn show_leaderboard<W: Write>(
+    stdout: &mut W,
+    keys: &mut Keys<AsyncReader>,
+    options: &Options,
+) -> io::Result<()> {
+    write!(stdout, "{}{}", clear::All, cursor::Goto(1, 1))?;
+    match &options.scores_path {
+        Some(path) => match Leaderboard::load_from_file(path) {
+            Ok(board) => {
+                let table = leaderboard_to_string(&board, 10).replace('\n', "\n\r");
+                stdout.write_all(table.as_bytes())?
+            }
+            Err(err) => write!(stdout, "Could not read {}: {err}\n\r", path.display())?,
+        },
+        None => write!(stdout, "High scores are not being kept.\n\r")?,
+    }
+    write!(stdout, "\n\rPress any key to return to the game.")?;
+    stdout.flush()?;
+    loop {
+        match keys.next() {
+            Some(key) => {
+                key?;
+                return Ok(());
+            }
+            None => thread::sleep(Duration::from_millis(10)),
+        }
+    }
+}
+
+/// The best `n` scores as a plain text table, one line per game.
+pub fn leaderboard_to_string(board: &Leaderboard, n: usize) -> String {
+    if board.scores.is_empty() {
+        return "No games recorded yet.\n".to_string();
+    }
+    let mut output = String::from("  #    Score    Max  Moves     Time  Date        Size   Seed\n");
+    for (rank, score) in board.top(n).iter().enumerate() {
+        let secs = score.duration.as_secs();
+        output.push_str(&format!(
+            "{:>3} {:>8} {:>6} {:>6} {:>5}:{:02}  {}  {:<5}  {}{}\n",
+            rank + 1,
+            score.result.score,
+            score.result.max_tile,
+            score.result.moves,
+            secs / 60,
+            secs % 60,
+            score.day(),
+            format!("{}x{}", score.rows, score.cols),
+            score.seed,
+            if score.result.undo_used {
+                " (undo)"
+            } else {
+                ""
+            }
+        ));
+    }
+    output
+}
+
+/// What the player chose on the end screen.
+enum EndChoice {
+    /// Keep playing after a win.
+    Continue,
+    /// Take back the losing move.
+    Undo,
+    Restart,
+    Quit,
+}
+
+/// Show the victory or game over box over the board and wait for the player's choice.
+fn end_screen<W: Write>(
+    stdout: &mut W,
+    keys: &mut Keys<AsyncReader>,
+    keymap: &Keymap,
+    theme: &Theme,
+    game: &Game,
+) -> io::Result<EndChoice> {
+    let won = game.status() == GameStatus::Win;
+    let result = game.result();
+    // Keeping going is not an action of its own, so it takes `c` unless that is bound.
+    let keep_going = if keymap.action(Char('c')).is_none() {
+        Char('c')
+    } else {
+        Char('\n')
+    };
+    let mut choices = Vec::new();
+    if won {
+        choices.push((Some(key_name(keep_going)), "keep playing"));
+    } else if game.can_undo() {
+        choices.push((keymap.label(Action::Undo), "undo"));
+    }
+    choices.push((keymap.label(Action::Restart), "new game"));
+    choices.push((keymap.label(Action::Quit), "quit"));
+    let choices: Vec<_> = choices
+        .into_iter()
+        .filter_map(|(key, text)| Some((key?, text)))
+        .collect();
+
+    // The key column is as wide as the longest key name.
+    let width = choices
+        .iter()
+        .map(|(key, _)| key.chars().count())
+        .max()
+        .unwrap_or(1);
+    let keys_rule = "─".repeat(width + 2);
+    let mut lines = vec![
+        format!("╔{}═══════════════╗", "═".repeat(width + 3)),
+        if won {
+            format!("║{keys_rule}┬You win!───────║")
+        } else {
+            format!("║{keys_rule}┬Game over──────║")
+        },
+        format!("║ {:width$} ┆ score {:>7} ║", "", result.score),
+        format!("║ {:width$} ┆ max   {:>7} ║", "", result.max_tile),
+        format!("║ {:width$} ┆ moves {:>7} ║", "", result.moves),
+        format!("║{keys_rule}┼───────────────║"),
+    ];
+    for (key, text) in &choices {
+        lines.push(format!("║ {key:width$} ┆ {text:13} ║"));
+    }
+    lines.push(format!("╚{}╧═══════════════╝", "═".repeat(width + 2)));
+
+    // Centre the box in the terminal, which is where the board is.
+    let draw_box = |stdout: &mut W| {
+        let (cols, rows) = terminal::size();
+        let x = cols.saturating_sub(width as u16 + 20) / 2 + 1;
+        let y = rows.saturating_sub(lines.len() as u16) / 2 + 1;
+        for (i, line) in lines.iter().enumerate() {
+            write!(
+                stdout,
+                "{}{}",
+                cursor::Goto(x, y + i as u16),
+                theme.lines(line)
+            )?;
+        }
+        stdout.flush()
+    };
+    draw_box(stdout)?;
+
+    loop {
+        match keys.next() {
+            Some(key) => {
+                let key = key?;
+                match (key, keymap.action(key)) {
+                    (key, _) if won && key == keep_going => return Ok(EndChoice::Continue),
+                    (_, Some(Action::Undo)) if !won && game.can_undo() => {
+                        return Ok(EndChoice::Undo)
+                    }
+                    (_, Some(Action::Restart)) => return Ok(EndChoice::Restart),
+                    (_, Some(Action::Quit)) => return Ok(EndChoice::Quit),
+                    _ => {}
+                }
+            }
+            None if terminal::resized() => {
+                write!(stdout, "{}", clear::All)?;
+                draw(stdout, theme, game, &[])?;
+                draw_box(stdout)?;
+            }
+            None => thread::sleep(Duration::from_millis(10)),
+        }
+    }
+}
+
+/// Step through a recorded game, automatically at `speed` steps per second or by hand.
+pub fn play_replay(replay: &Replay, speed: f64, theme: &Theme) -> io::Result<()> {
+    let stdout = io::stdout().lock();
+    let mut stdout = stdout.into_raw_mode()?;
+    let mut keys = termion::async_stdin().keys();
+
+    let frames = replay.frames();
+    let mut index = 0;
+    let mut playing = true;
+    let mut speed = speed;
+    let mut last_step = Instant::now();
+    let mut dirty = true;
+    terminal::watch_resize();
+
+    write!(stdout, "{}", clear::All)?;
+    loop {
+        if terminal::resized() {
+            write!(stdout, "{}", clear::All)?;
+            dirty = true;
+        }
+        match keys.next() {
+            Some(Ok(key)) => {
+                match key {
+                    Char('l') | Key::Right => index = (index + 1).min(frames.len() - 1),
+                    Char('h') | Key::Left => index = index.saturating_sub(1),
+                    Char('g') | Key::Home => index = 0,
+                    Char('G') | Key::End => index = frames.len() - 1,
+                    Char(' ') => playing = !playing,
+                    Char('+') => speed = (speed * 2.0).min(64.0),
+                    Char('-') => speed = (speed / 2.0).max(0.25),
+                    Char('q') => break,
+                    _ => {}
+                }
+                last_step = Instant::now();
+                dirty = true;
+            }
+            Some(Err(err)) => return Err(err),
+            None => thread::sleep(Duration::from_millis(10)),
+        }
+
+        if playing && last_step.elapsed().as_secs_f64() >= 1.0 / speed {
+            last_step = Instant::now();
+            if index + 1 < frames.len() {
+                index += 1;
+            } else {
+                playing = false;
+            }
+            dirty = true;
+        }
+
+        if dirty {
+            let frame = &frames[index];
+            let spawned: Vec<_> = frame
+                .spawns
+                .iter()
+                .map(|spawn| (spawn.row, spawn.col))
+                .collect();
+            draw_board(
+                &mut stdout,
+                theme,
+                &frame.board,
+                frame.score,
+                replay.seed,
+                &spawned,
+            )?;
+            let state = if playing { "playing" } else { "paused" };
+            let text = format!(
+                "Step {}/{}  {speed} steps/s  {state}\n\
+                 h/l step  space play/pause  +/- speed  q quit",
+                index,
+                frames.len() - 1
+            );
+            note(&mut stdout, &frame.board, &text)?;
+            dirty = false;
+        }
+    }
+
+    write!(stdout, "{}", style::Reset)?;
+    stdout.flush()
+}
+
+/// Race the player at the other end of `race` until the race is decided and the player
+/// quits, showing the opponent's board beside ours.
+///
+/// Returns the winner, or `None` if the player left before the race was decided.
+pub fn play_race(
+    game: &mut Game,
+    options: &Options,
+    race: &mut Race,
+) -> io::Result<Option<Winner>> {
+    let stdout = io::stdout().lock();
+    let mut stdout = stdout.into_raw_mode()?;
+    let mut keys = termion::async_stdin().keys();
+    terminal::watch_resize();
+
+    let mut replay = options.record_path.as_ref().map(|_| Replay::record(game));
+    let mut ai = options.ai.player(options.ai_depth, game.seed());
+    let mut autoplay = options.autoplay;
+    let mut last_move = Instant::now();
+    // Both sides start from the same position.
+    let mut opponent = Progress::of(game);
+    let mut winner = None;
+    let mut left = false;
+    let mut forfeit = false;
+    let mut dirty = true;
+
+    write!(stdout, "{}", clear::All)?;
+    options.publish(game);
+    loop {
+        if terminal::resized() {
+            write!(stdout, "{}", clear::All)?;
+            dirty = true;
+        }
+        // `try_recv` keeps returning `Quit` once the other side has gone.
+        while !left {
+            let Some(message) = race.try_recv() else {
+                break;
+            };
+            match message {
+                Message::Progress(progress) => opponent = progress,
+                Message::Over { winner: decided } if !race.host => winner = Some(decided),
+                Message::Quit => left = true,
+                _ => {}
+            }
+            dirty = true;
+        }
+        if left && winner.is_none() {
+            forfeit = true;
+            winner = Some(race.side());
+        }
+        if race.host && winner.is_none() {
+            winner = race::decide(&Progress::of(game), &opponent);
+            if let Some(winner) = winner {
+                let _ = race.send(&Message::Over { winner });
+            }
+        }
+
+        let playing = winner.is_none() && game.status() == GameStatus::Continue;
+        let direction = match keys
+            .next()
+            .transpose()?
+            .map(|key| options.keymap.action(key))
+        {
+            Some(Some(Action::Move(direction))) if playing => direction,
+            Some(Some(Action::Autoplay)) => {
+                autoplay = !autoplay;
+                last_move = Instant::now();
+                continue;
+            }
+            Some(Some(Action::Quit)) => break,
+            Some(_) => continue,
+            None if playing && autoplay && last_move.elapsed() >= options.autoplay_delay => {
+                match ai.choose(game) {
+                    Some(direction) => direction,
+                    None => continue,
+                }
+            }
+            None => {
+                if dirty {
+                    draw_race(
+                        &mut stdout,
+                        &options.theme,
+                        game,
+                        &opponent,
+                        &race_status(race, game, winner, forfeit),
+                    )?;
+                    dirty = false;
+                }
+                thread::sleep(Duration::from_millis(10));
+                continue;
+            }
+        };
+        last_move = Instant::now();
+        let (_, events) = game.move_events(direction);
+        if events.is_empty() {
+            continue;
+        }
+        if let Some(replay) = &mut replay {
+            replay.push_move(direction, &events);
+        }
+        options.publish(game);
+        // A lost connection shows up as `Quit` on the next read.
+        let _ = race.send(&Message::Progress(Progress::of(game)));
+        dirty = true;
+    }
+
+    if !left {
+        let _ = race.send(&Message::Quit);
+    }
+    if let (Some(path), Some(replay)) = (&options.record_path, &replay) {
+        replay.save_to_file(path)?;
+    }
+    write!(
+        stdout,
+        "{}{}{}",
+        clear::All,
+        style::Reset,
+        cursor::Goto(1, 1)
+    )?;
+    stdout.flush()?;
+    Ok(winner)
+}
+
+/// How the race stands, for the line below the score.
+fn race_status(race: &Race, game: &Game, winner: Option<Winner>, forfeit: bool) -> String {
+    match winner {
+        Some(_) if forfeit => "Your opponent left: you win! Press q to quit.".to_string(),
+        Some(Winner::Draw) => "The race is a draw. Press q to quit.".to_string(),
+        Some(winner) if winner == race.side() => "You win the race! Press q to quit.".to_string(),
+        Some(_) => "You lose the race. Press q to quit.".to_string(),
+        None if game.status() == GameStatus::Lost => {
+            "No moves left: waiting for your opponent.".to_string()
+        }
+        None => format!("First to {} wins.", game.target()),
+    }
+}
+
+/// Draw our board with the opponent's, compact, to its right.
+fn draw_race<W: Write>(
+    stdout: &mut W,
+    theme: &Theme,
+    game: &Game,
+    opponent: &Progress,
+    status: &str,
+) -> io::Result<()> {
+    let board = game.board();
+    let mut theirs = Board::new(board.rows(), board.cols());
+    for r in 0..board.rows() {
+        for c in 0..board.cols() {
+            let val = opponent.board.get(r).and_then(|row| row.get(c)).copied();
+            theirs.set(r, c, val.filter(|&val| val > 0));
+        }
+    }
+    let panel = Layout {
+        width: theirs.max_tile().to_string().len().max(4) + 2,
+        height: 1,
+        x: 1,
+        y: 1,
+    };
+    let panel_cols = panel.size(&theirs).0 + RACE_GAP;
+    let (cols, rows) = terminal::size();
+    let layout = match Layout::fit(board, (cols.saturating_sub(panel_cols as u16), rows)) {
+        Ok(layout) => layout,
+        Err((cols, rows)) => {
+            write!(
+                stdout,
+                "{}{}Terminal too small: the race needs {}x{rows}.",
+                clear::All,
+                cursor::Goto(1, 1),
+                cols + panel_cols
+            )?;
+            return stdout.flush();
+        }
+    };
+    write_board(
+        stdout,
+        layout,
+        &render_board(theme, board, layout, |_, _| Look::Normal),
+    )?;
+    let y = layout.y + layout.size(board).1 as u16;
+    let score = theme.score.paint(&format!("Score: {}", game.score()));
+    let seed = format!("Seed: {}", game.seed());
+    for (i, line) in [score, seed, status.to_string()].iter().enumerate() {
+        write_line(stdout, layout.x, y + i as u16, line)?;
+    }
+    write!(stdout, "{}", clear::AfterCursor)?;
+
+    let panel = Layout {
+        x: layout.x + (layout.size(board).0 + RACE_GAP) as u16,
+        y: layout.y,
+        ..panel
+    };
+    let rendered = render_board(theme, &theirs, panel, |_, _| Look::Normal);
+    let score = theme.score.paint(&format!("Opponent: {}", opponent.score));
+    let moves = format!("Moves: {}", opponent.moves);
+    let lines = rendered
+        .split_terminator("\n\r")
+        .chain([score.as_str(), moves.as_str()]);
+    for (i, line) in lines.enumerate() {
+        write!(
+            stdout,
+            "{}{line}",
+            cursor::Goto(panel.x, panel.y + i as u16)
+        )?;
+    }
+    stdout.flush()
+}
+
+/// Watch a broadcast game until the player quits.
+pub fn watch(spectator: &mut Spectator, theme: &Theme) -> io::Result<()> {
+    let stdout = io::stdout().lock();
+    let mut stdout = stdout.into_raw_mode()?;
+    let mut keys = termion::async_stdin().keys();
+    terminal::watch_resize();
+
+    let mut state = None;
+    let mut over = false;
+    let mut dirty = true;
+    write!(stdout, "{}", clear::All)?;
+    loop {
+        if terminal::resized() {
+            write!(stdout, "{}", clear::All)?;
+            dirty = true;
+        }
+        if let Some(latest) = spectator.latest() {
+            // Positions that cannot be drawn are skipped.
+            if let Some(board) = board_from_rows(&latest.board) {
+                state = Some((board, latest));
+                dirty = true;
+            }
+        }
+        if spectator.is_over() && !over {
+            over = true;
+            dirty = true;
+        }
+        match keys.next().transpose()? {
+            Some(Char('q') | Key::Esc) => break,
+            Some(_) => {}
+            None => thread::sleep(Duration::from_millis(10)),
+        }
+
+        if !dirty {
+            continue;
+        }
+        dirty = false;
+        let Some((board, latest)) = &state else {
+            let text = if over {
+                "The broadcast ended before the game could be shown."
+            } else {
+                "Waiting for the game..."
+            };
+            write!(stdout, "{}{}{text}", clear::All, cursor::Goto(1, 1))?;
+            stdout.flush()?;
+            continue;
+        };
+        draw_board(&mut stdout, theme, board, latest.score, latest.seed, &[])?;
+        let status = match latest.status.as_str() {
+            _ if over => "The game has ended",
+            "won" => "Won",
+            "lost" => "No moves left",
+            _ => "Watching",
+        };
+        let text = format!("{status}, {} moves. Press q to quit.", latest.moves);
+        note(&mut stdout, board, &text)?;
+    }
+
+    write!(
+        stdout,
+        "{}{}{}",
+        clear::All,
+        style::Reset,
+        cursor::Goto(1, 1)
+    )?;
+    stdout.flush()
+}
+
+/// A board holding the values of `rows`, 0 for empty, if it is a size we can show.
+fn board_from_rows(rows: &[Vec<u32>]) -> Option<Board> {
+    let cols = rows.first()?.len();
+    let sizes = MIN_SIZE..=MAX_SIZE;
+    if !sizes.contains(&rows.len()) || !sizes.contains(&cols) {
+        return None;
+    }
+    let mut board = Board::new(rows.len(), cols);
+    for (r, row) in rows.iter().enumerate() {
+        if row.len() != cols {
+            return None;
+        }
+        for (c, &val) in row.iter().enumerate() {
+            board.set(r, c, Some(val).filter(|&val| val > 0));
+        }
+    }
+    Some(board)
+}
+
+/// Redraw the board, score and seed in the middle of the terminal.
+fn draw<W: Write>(
+    stdout: &mut W,
+    theme: &Theme,
+    game: &Game,
+    spawned: &[(usize, usize)],
+) -> io::Result<()> {
+    draw_board(
+        stdout,
+        theme,
+        game.board(),
+        game.score(),
+        game.seed(),
+        spawned,
+    )
+}
+
+fn draw_board<W: Write>(
+    stdout: &mut W,
+    theme: &Theme,
+    board: &Board,
+    score: u32,
+    seed: u64,
+    spawned: &[(usize, usize)],
+) -> io::Result<()> {
+    let layout = match Layout::current(board) {
+        Ok(layout) => layout,
+        Err((cols, rows)) => {
+            write!(
+                stdout,
+                "{}{}Terminal too small: the board needs {cols}x{rows}.",
+                clear::All,
+                cursor::Goto(1, 1)
+            )?;
+            return stdout.flush();
+        }
+    };
+    let look = |r, c| {
+        if spawned.contains(&(r, c)) {
+            Look::Spawned
+        } else {
+            Look::Normal
+        }
+    };
+    write_board(stdout, layout, &render_board(theme, board, layout, look))?;
+    let y = layout.y + layout.size(board).1 as u16;
+    let score = theme.score.paint(&format!("Score: {score}"));
+    let seed = format!("Seed: {seed}");
+    for (i, line) in [score, seed].iter().enumerate() {
+        write_line(stdout, layout.x, y + i as u16, line)?;
+    }
+    // Leave the notes, but nothing a bigger layout left further down.
+    write!(
+        stdout,
+        "{}{}",
+        cursor::Goto(1, y + FOOTER as u16),
+        clear::AfterCursor
+    )?;
+    stdout.flush()
+}
+
+/// Show a message below the score, one line of the terminal per line of `text`.
+fn note<W: Write>(stdout: &mut W, board: &Board, text: &str) -> io::Result<()> {
+    let Ok(layout) = Layout::current(board) else {
+        return Ok(());
+    };
+    let y = layout.y + layout.size(board).1 as u16 + 2;
+    for (i, line) in text.lines().enumerate() {
+        write_line(stdout, layout.x, y + i as u16, line)?;
+    }
+    stdout.flush()
+}
+
+/// Replace line `y` of the terminal with `text` starting at column `x`.
+fn write_line<W: Write>(stdout: &mut W, x: u16, y: u16, text: &str) -> io::Result<()> {
+    write!(
+        stdout,
+        "{}{}{}{}",
+        cursor::Goto(1, y),
+        clear::CurrentLine,
+        cursor::Goto(x, y),
+        text
+    )
+}
+
+/// Lines below the board: the score, the seed and two lines of notes.
+const FOOTER: usize = 4;
+/// Columns between our board and the opponent's in a race.
+const RACE_GAP: usize = 3;
+
+/// Where the board goes on screen and how big its cells are.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Layout {
+    /// Columns in each cell.
+    pub(crate) width: usize,
+    /// Lines in each cell. Cells one line high are compact: no lines separate the rows.
+    pub(crate) height: usize,
+    /// Top-left corner of the board.
+    pub(crate) x: u16,
+    pub(crate) y: u16,
+}
+
+impl Layout {
+    /// The biggest layout of `board` that fits the terminal, centred in it.
+    fn current(board: &Board) -> Result<Layout, (usize, usize)> {
+        Layout::fit(board, terminal::size())
+    }
+
+    /// The biggest layout of `board` that fits in `cols` by `rows` characters, centred,
+    /// or the size of the smallest layout if none fits.
+    ///
+    /// Cells are about twice as wide as they are high, so that they look square, and
+    /// always hold the widest tile with a space either side.
+    pub(crate) fn fit(board: &Board, (cols, rows): (u16, u16)) -> Result<Layout, (usize, usize)> {
+        let digits = board.max_tile().to_string().len();
+        let sizes = [7, 5, 3]
+            .map(|height| ((2 * height).max(digits + 2), height))
+            .into_iter()
+            .chain([(digits.max(4) + 2, 1)]);
+        let mut needed = (0, 0);
+        for (width, height) in sizes {
+            let layout = Layout {
+                width,
+                height,
+                x: 1,
+                y: 1,
+            };
+            let (board_cols, board_rows) = layout.size(board);
+            needed = (board_cols, board_rows + FOOTER);
+            if let (Some(x), Some(y)) = (
+                (cols as usize).checked_sub(needed.0),
+                (rows as usize).checked_sub(needed.1),
+            ) {
+                return Ok(Layout {
+                    x: (x / 2 + 1) as u16,
+                    y: (y / 2 + 1) as u16,
+                    ..layout
+                });
+            }
+        }
+        Err(needed)
+    }
+
+    fn compact(&self) -> bool {
+        self.height == 1
+    }
+
+    /// Columns and rows taken by the drawn board.
+    fn size(&self, board: &Board) -> (usize, usize) {
+        let cols = (self.width + 1) * board.cols() + 1;
+        let rows = if self.compact() {
+            board.rows() + 2
+        } else {
+            (self.height + 1) * board.rows() + 1
+        };
+        (cols, rows)
+    }
+
+    /// Terminal position of the line of cell `(r, c)` that holds its value.
+    fn cell_position(&self, (r, c): (usize, usize)) -> (u16, u16) {
+        let x = self.x as usize + c * (self.width + 1) + 1;
+        let y = if self.compact() {
+            self.y as usize + r + 1
+        } else {
+            self.y as usize + r * (self.height + 1) + 1 + self.height / 2
+        };
+        (x as u16, y as u16)
+    }
+}
+
+/// How a tile is drawn.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Look {
+    Normal,
+    /// The tile spawned by the last move.
+    Spawned,
+    /// A tile that has just merged.
+    Popped,
+    /// A spawned tile fading in.
+    Faded,
+}
+
+/// A tile's value centred in `width` characters.
+fn tile_to_string(theme: &Theme, val: u32, width: usize, look: Look) -> String {
+    let mut style = theme.tile(val);
+    let extra = match look {
+        Look::Normal => String::new(),
+        Look::Spawned => {
+            style.bold = true;
+            String::new()
+        }
+        Look::Popped => style::Invert.to_string(),
+        Look::Faded => style::Faint.to_string(),
+    };
+    format!("{}{extra}{val:^width$}{}", style.codes(), style::Reset)
+}
+
+fn render_board(
+    theme: &Theme,
+    board: &Board,
+    layout: Layout,
+    look: impl Fn(usize, usize) -> Look,
+) -> String {
+    // game board display (4x4, cells 6 wide and 3 high):
+    // ┌──────┬──────┬──────┬──────┐
+    // │      │      │      │      │
+    // │  2   │ 1024 │  8   │  16  │
+    // │      │      │      │      │
+    // ├──────┼──────┼──────┼──────┤
+    // │      │      │      │      │
+    // │  2   │ 1024 │  8   │  16  │
+    // │      │      │      │      │
+    // ├──────┼──────┼──────┼──────┤
+    // │      │      │      │      │
+    // │  2   │ 1024 │  8   │  16  │
+    // │      │      │      │      │
+    // ├──────┼──────┼──────┼──────┤
+    // │      │      │      │      │
+    // │  2   │ 1024 │  8   │  16  │
+    // │      │      │      │      │
+    // └──────┴──────┴──────┴──────┘
+    // Compact boards leave out the blank lines and the borders between rows.
+    // Lines end in \n\r; a tile's background fills its whole cell.
+
+    let cols = board.cols();
+    let width = layout.width;
+    let border = |left: &str, mid: &str, right: &str| {
+        let mut line = String::from(left);
+        line.push_str(&vec!["─".repeat(width); cols].join(mid));
+        line.push_str(right);
+        theme.grid.paint(&theme.lines(&line)) + "\n\r"
+    };
+    let bar = theme.grid.paint(&theme.lines("│"));
+    let padding = " ".repeat(width);
+
+    let (board_cols, board_rows) = layout.size(board);
+    let mut output = String::with_capacity(board_rows * (board_cols + 2));
+    output.push_str(&border("┌", "┬", "┐"));
+
+    for (board_row, row) in board.iter_rows().enumerate() {
+        if board_row > 0 && !layout.compact() {
+            output.push_str(&border("├", "┼", "┤"));
+        }
+        let mut blank = bar.clone();
+        let mut middle = bar.clone();
+        for (col, val) in row.iter().enumerate() {
+            match *val {
+                Some(val) => {
+                    blank.push_str(&theme.tile(val).paint(&padding));
+                    middle.push_str(&tile_to_string(theme, val, width, look(board_row, col)));
+                }
+                None => {
+                    blank.push_str(&theme.empty.paint(&padding));
+                    middle.push_str(&theme.empty.paint(&padding));
+                }
+            }
+            blank.push_str(&bar);
+            middle.push_str(&bar);
+        }
+        for i in 0..layout.height {
+            output.push_str(if i == layout.height / 2 {
+                &middle
+            } else {
+                &blank
+            });
+            output.push_str("\n\r");
+        }
+    }
+    output.push_str(&border("└", "┴", "┘"));
+
+    output
+}
+
+/// Draws the frames between two positions of the board.
+///
+/// Keys pressed meanwhile are queued in `pending`, and the first one cuts the animation
+/// short so a fast player is never kept waiting.
+struct Animation<'a> {
+    theme: &'a Theme,
+    keys: &'a mut Keys<AsyncReader>,
+    pending: &'a mut VecDeque<Key>,
+    /// How long each frame stays on screen.
+    frame: Duration,
+}
+
+impl Animation<'_> {
+    /// Slide the tiles of `before` along their moves, pop the merged tiles of `after`
+    /// and fade in the spawned one. The caller draws the final position.
+    fn play<W: Write>(
+        &mut self,
+        stdout: &mut W,
+        before: &Board,
+        after: &Board,
+        events: &[Event],
+    ) -> io::Result<()> {
+        let Ok(layout) = Layout::current(after) else {
+            return Ok(());
+        };
+        let mut tiles = Vec::new();
+        let mut popped = Vec::new();
+        let mut spawned = Vec::new();
+        for event in events {
+            match *event {
+                Event::Moved { from, to, value } => tiles.push((from, to, value)),
+                Event::Merged { at, .. } => popped.push(at),
+                Event::Spawned { at, .. } => spawned.push(at),
+                Event::Scored(_) => {}
+            }
+        }
+        for (r, row) in before.iter_rows().enumerate() {
+            for (c, val) in row.iter().enumerate() {
+                let moved = tiles.iter().any(|&(from, ..)| from == (r, c));
+                if let (Some(val), false) = (val, moved) {
+                    tiles.push(((r, c), (r, c), *val));
+                }
+            }
+        }
+
+        let empty = Board::new(after.rows(), after.cols());
+        for step in 1..=SLIDE_FRAMES {
+            let t = f64::from(step) / f64::from(SLIDE_FRAMES + 1);
+            write_board(
+                stdout,
+                layout,
+                &render_board(self.theme, &empty, layout, |_, _| Look::Normal),
+            )?;
+            for &(from, to, val) in &tiles {
+                let (x0, y0) = layout.cell_position(from);
+                let (x1, y1) = layout.cell_position(to);
+                let x = f64::from(x0) + (f64::from(x1) - f64::from(x0)) * t;
+                let y = f64::from(y0) + (f64::from(y1) - f64::from(y0)) * t;
+                write!(
+                    stdout,
+                    "{}{}",
+                    cursor::Goto(x.round() as u16, y.round() as u16),
+                    tile_to_string(self.theme, val, layout.width, Look::Normal)
+                )?;
+            }
+            if self.wait(stdout)? {
+                return Ok(());
+            }
+        }
+
+        let mut merged = after.clone();
+        for &(r, c) in &spawned {
+            merged.set(r, c, None);
+        }
+        let pop = |r, c| {
+            if popped.contains(&(r, c)) {
+                Look::Popped
+            } else {
+                Look::Normal
+            }
+        };
+        write_board(
+            stdout,
+            layout,
+            &render_board(self.theme, &merged, layout, pop),
+        )?;
+        if self.wait(stdout)? {
+            return Ok(());
+        }
+        let fade = |r, c| {
+            if spawned.contains(&(r, c)) {
+                Look::Faded
+            } else {
+                Look::Normal
+            }
+        };
+        write_board(
+            stdout,
+            layout,
+            &render_board(self.theme, after, layout, fade),
+        )?;
+        self.wait(stdout)?;
+        Ok(())
+    }
+
+    /// Show the frame just drawn, returning `true` if a key was pressed meanwhile.
+    fn wait<W: Write>(&mut self, stdout: &mut W) -> io::Result<bool> {
+        stdout.flush()?;
+        let shown = Instant::now();
+        while shown.elapsed() < self.frame {
+            for key in self.keys.by_ref() {
+                self.pending.push_back(key?);
+            }
+            if !self.pending.is_empty() {
+                return Ok(true);
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+        Ok(false)
+    }
+}
+
+/// Write a rendered board at its place in `layout`, clearing everything above it and
+/// to either side, where a board drawn with another layout may have been.
+fn write_board<W: Write>(stdout: &mut W, layout: Layout, board: &str) -> io::Result<()> {
+    write!(
+        stdout,
+        "{}{}",
+        cursor::Goto(1, layout.y),
+        clear::BeforeCursor
+    )?;
+    for (i, line) in board.split_terminator("\n\r").enumerate() {
+        write_line(stdout, layout.x, layout.y + i as u16, line)?;
+    }
+    Ok(())
+}